@@ -0,0 +1,296 @@
+//! Latency measurements of blocking on futures.
+//!
+//! A [`Harness`] drives a future to completion over and over, timing how long each wake takes to
+//! turn into a poll, which is where spinning, parking and the other idle strategies of
+//! [`Runner::on_idle`](crate::Runner::on_idle) differ.
+//!
+//! # Example
+//!
+//! ```
+//! use pollster::{bench::Harness, IdleDecision};
+//!
+//! let parked = Harness::new().iterations(200).run(|| pollster::unblock(|| ()));
+//! let spinning = Harness::new()
+//!     .iterations(200)
+//!     .on_idle(|_| IdleDecision::Poll)
+//!     .run(|| pollster::unblock(|| ()));
+//! println!("parked: {}\nspinning: {}", parked, spinning);
+//! assert_eq!(parked.calls(), 200);
+//! ```
+
+use crate::{IdleContext, IdleDecision, Signal};
+use std::{
+    fmt,
+    future::Future,
+    panic,
+    pin::pin,
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Wake, Waker},
+    thread,
+    time::{Duration, Instant},
+};
+
+type IdleCallback = Box<dyn FnMut(IdleContext) -> IdleDecision + Send>;
+
+// Wakes the harness thread, noting when the first wake since the last poll arrived.
+struct BenchWaker {
+    signal: Signal,
+    woken_at: Mutex<Option<Instant>>,
+}
+
+impl BenchWaker {
+    fn take(&self) -> Option<Instant> {
+        self.woken_at.lock().unwrap_or_else(PoisonError::into_inner).take()
+    }
+
+    fn is_woken(&self) -> bool {
+        self.woken_at.lock().unwrap_or_else(PoisonError::into_inner).is_some()
+    }
+}
+
+impl Wake for BenchWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let now = Instant::now();
+        self.woken_at.lock().unwrap_or_else(PoisonError::into_inner).get_or_insert(now);
+        self.signal.notify();
+    }
+}
+
+/// Runs a future repeatedly and reports how quickly it is polled after being woken.
+///
+/// Each call creates a fresh future and blocks on it, much like [`block_on`](crate::block_on),
+/// but with one waker that is created up front and reused for every call, so setting it up isn't
+/// measured. The calls run on a thread of their own that never pumps a run loop or message queue,
+/// and a number of warmup calls, 100 by default, run first and are left out of the report. They
+/// start whatever background threads the future relies on, like the timer thread, so their
+/// startup isn't measured either.
+pub struct Harness {
+    iterations: u64,
+    warmup: u64,
+    on_idle: Option<IdleCallback>,
+}
+
+impl Harness {
+    /// Create a harness that measures 1000 calls after 100 warmup calls, parking while the future
+    /// is pending.
+    pub fn new() -> Self {
+        Self {
+            iterations: 1000,
+            warmup: 100,
+            on_idle: None,
+        }
+    }
+
+    /// Set how many calls are measured.
+    pub fn iterations(mut self, iterations: u64) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Set how many calls run before measuring starts.
+    pub fn warmup(mut self, warmup: u64) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    /// Decide what to do whenever the future is pending, as with
+    /// [`Runner::on_idle`](crate::Runner::on_idle). The callback is told about the current call
+    /// only.
+    pub fn on_idle(mut self, f: impl FnMut(IdleContext) -> IdleDecision + Send + 'static) -> Self {
+        self.on_idle = Some(Box::new(f));
+        self
+    }
+
+    /// Block on a future from `make` once per call, returning the measurements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a future is pending after dropping every clone of its waker without waking it,
+    /// and resumes any panic from `make` or the futures.
+    #[track_caller]
+    pub fn run<M, F>(&mut self, make: M) -> Report
+    where
+        M: FnMut() -> F + Send,
+        F: Future,
+    {
+        let Self {
+            iterations,
+            warmup,
+            on_idle,
+        } = self;
+        let (iterations, warmup) = (*iterations, *warmup);
+        thread::scope(|scope| {
+            let worker = thread::Builder::new()
+                .name("pollster-bench".into())
+                .spawn_scoped(scope, move || measure(iterations, warmup, on_idle.as_mut(), make))
+                .expect("failed to spawn benchmark thread");
+            worker.join().unwrap_or_else(|payload| panic::resume_unwind(payload))
+        })
+    }
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Harness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Harness")
+            .field("iterations", &self.iterations)
+            .field("warmup", &self.warmup)
+            .field("on_idle", &self.on_idle.is_some())
+            .finish()
+    }
+}
+
+fn measure<M, F>(
+    iterations: u64,
+    warmup: u64,
+    mut on_idle: Option<&mut IdleCallback>,
+    mut make: M,
+) -> Report
+where
+    M: FnMut() -> F,
+    F: Future,
+{
+    let wake = Arc::new(BenchWaker {
+        signal: Signal::detached(None),
+        woken_at: Mutex::new(None),
+    });
+    let waker = Waker::from(Arc::clone(&wake));
+    let mut context = Context::from_waker(&waker);
+
+    let mut report = Report {
+        calls: iterations,
+        polls: 0,
+        latencies: Vec::new(),
+        elapsed: Duration::ZERO,
+    };
+    for call in 0..warmup + iterations {
+        let measured = call >= warmup;
+        let start = Instant::now();
+        let mut fut = pin!(make());
+        wake.take();
+        let mut polls = 0;
+        loop {
+            let polled_at = Instant::now();
+            if let (Some(woken_at), true) = (wake.take(), measured) {
+                report.latencies.push(polled_at.saturating_duration_since(woken_at));
+            }
+            polls += 1;
+            if fut.as_mut().poll(&mut context).is_ready() {
+                break;
+            }
+
+            let decision = on_idle.as_mut().map_or(IdleDecision::Park, |on_idle| {
+                on_idle(IdleContext {
+                    polls,
+                    elapsed: start.elapsed(),
+                })
+            });
+            match decision {
+                IdleDecision::Poll => {}
+                IdleDecision::ParkTimeout(timeout) => wake.signal.wait_timeout(timeout),
+                IdleDecision::Park => {
+                    while !wake.is_woken() {
+                        // Held only here and by `waker`.
+                        if Arc::strong_count(&wake) <= 2 {
+                            panic!(
+                                "benchmark deadlocked: `{}` is pending but dropped its waker without waking it",
+                                crate::type_label::<F>(),
+                            );
+                        }
+                        wake.signal.wait_timeout(Duration::from_millis(100));
+                    }
+                }
+            }
+        }
+        if measured {
+            report.polls += polls;
+            report.elapsed += start.elapsed();
+        }
+    }
+    report.latencies.sort_unstable();
+    report
+}
+
+/// Measurements returned by [`Harness::run`].
+#[derive(Debug, Clone)]
+pub struct Report {
+    calls: u64,
+    polls: u64,
+    // Sorted.
+    latencies: Vec<Duration>,
+    elapsed: Duration,
+}
+
+impl Report {
+    /// How many calls were measured.
+    pub fn calls(&self) -> u64 {
+        self.calls
+    }
+
+    /// The mean number of times each future was polled before completing.
+    pub fn polls_per_call(&self) -> f64 {
+        if self.calls == 0 {
+            return 0.0;
+        }
+        self.polls as f64 / self.calls as f64
+    }
+
+    /// How many wakes were followed by a poll, which is how many latencies were recorded.
+    pub fn wakes(&self) -> usize {
+        self.latencies.len()
+    }
+
+    /// The time from a wake to the poll that followed it, at `percentile` (from 0 to 100) of all
+    /// wakes. Returns `None` if no future was woken.
+    ///
+    /// Several wakes before one poll count once, from the earliest.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentile` is not between 0 and 100.
+    pub fn wake_to_poll(&self, percentile: f64) -> Option<Duration> {
+        assert!((0.0..=100.0).contains(&percentile), "percentile must be between 0 and 100");
+        let rank = (percentile / 100.0 * self.latencies.len() as f64).ceil() as usize;
+        self.latencies.get(rank.saturating_sub(1)).copied()
+    }
+
+    /// The median wake-to-poll latency.
+    pub fn p50(&self) -> Option<Duration> {
+        self.wake_to_poll(50.0)
+    }
+
+    /// The 99th percentile wake-to-poll latency.
+    pub fn p99(&self) -> Option<Duration> {
+        self.wake_to_poll(99.0)
+    }
+
+    /// The total time the measured calls took.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} calls in {:?}, {:.2} polls/call",
+            self.calls,
+            self.elapsed,
+            self.polls_per_call()
+        )?;
+        match (self.p50(), self.p99()) {
+            (Some(p50), Some(p99)) => write!(f, ", wake-to-poll p50 {:?} p99 {:?}", p50, p99),
+            _ => write!(f, ", no wakes"),
+        }
+    }
+}
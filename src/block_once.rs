@@ -0,0 +1,90 @@
+use crate::Signal;
+use std::{
+    error::Error,
+    fmt,
+    future::Future,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// A future packaged with everything needed to block on it, to be run exactly once, on whichever
+/// thread gets to it.
+///
+/// The waker's allocation is made when the `BlockOnce` is created, so a dedicated waiter thread
+/// only has to call [`run`](BlockOnce::run). The value can be sent to, or shared with, other
+/// threads; only the first call to `run` drives the future and later calls fail with
+/// [`AlreadyRun`].
+///
+/// # Example
+///
+/// ```
+/// use std::{sync::Arc, thread};
+/// use pollster::BlockOnce;
+///
+/// let work = Arc::new(BlockOnce::new(async { 6 * 7 }));
+/// let waiter = thread::spawn({
+///     let work = Arc::clone(&work);
+///     move || work.run()
+/// });
+/// let here = work.run();
+/// let there = waiter.join().unwrap();
+/// // Exactly one of the two calls ran the future.
+/// assert!(matches!((here, there), (Ok(42), Err(_)) | (Err(_), Ok(42))));
+/// ```
+pub struct BlockOnce<F> {
+    inner: Mutex<Option<(F, Arc<Signal>)>>,
+}
+
+impl<F: Future> BlockOnce<F> {
+    /// Package `fut` to be blocked on later.
+    pub fn new(fut: F) -> Self {
+        Self {
+            inner: Mutex::new(Some((fut, Arc::new(Signal::detached(None))))),
+        }
+    }
+
+    /// Block the current thread until the future is ready, like [`block_on`](crate::block_on).
+    ///
+    /// Fails without blocking if the future has already been run, or is being run on another
+    /// thread.
+    #[track_caller]
+    pub fn run(&self) -> Result<F::Output, AlreadyRun> {
+        let taken = self.inner.lock().unwrap_or_else(PoisonError::into_inner).take();
+        let (fut, mut signal) = taken.ok_or(AlreadyRun(()))?;
+        Ok(crate::block_on_inner(
+            fut,
+            None,
+            move || {
+                // Nothing else holds the signal yet, so it can be tied to the thread that runs.
+                if let Some(signal) = Arc::get_mut(&mut signal) {
+                    signal.attach_current_thread();
+                }
+                signal
+            },
+            None,
+        ))
+    }
+
+    /// Whether [`run`](BlockOnce::run) has been called.
+    pub fn has_run(&self) -> bool {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner).is_none()
+    }
+}
+
+impl<F> fmt::Debug for BlockOnce<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let has_run = self.inner.lock().unwrap_or_else(PoisonError::into_inner).is_none();
+        f.debug_struct("BlockOnce").field("has_run", &has_run).finish()
+    }
+}
+
+/// Returned by [`BlockOnce::run`] when the future has already been run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlreadyRun(());
+
+impl fmt::Display for AlreadyRun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BlockOnce has already been run")
+    }
+}
+
+impl Error for AlreadyRun {}
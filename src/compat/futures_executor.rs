@@ -0,0 +1,34 @@
+//! The commonly used parts of the `futures::executor` API, backed by pollster.
+//!
+//! Switching an import from `futures::executor` to this module keeps call sites compiling as long
+//! as they only call the functions and inherent methods below. These types don't implement the
+//! `futures::task::Spawn` and `LocalSpawn` traits, so code that spawns through them, or through
+//! `SpawnExt` and `LocalSpawnExt`, needs porting to the inherent methods:
+//!
+//! - [`block_on`] and [`block_on_stream`] block the current thread just like their `futures`
+//!   counterparts.
+//! - [`LocalPool`] and [`LocalSpawner`] run `!Send` futures on the thread driving the pool.
+//!   `spawn_local` is an inherent method here rather than part of the `LocalSpawnExt` trait, and
+//!   fails with this crate's [`SpawnError`] rather than the one in `futures::task`.
+//! - [`ThreadPool`] and [`ThreadPoolBuilder`] run `Send` futures on worker threads, with
+//!   `spawn_ok` as in `futures`.
+//!
+//! # Example
+//!
+//! ```
+//! use pollster::compat::futures_executor::{block_on, ThreadPool};
+//!
+//! let pool = ThreadPool::new().unwrap();
+//! let (tx, rx) = std::sync::mpsc::channel();
+//! pool.spawn_ok(async move { tx.send(42).unwrap() });
+//! assert_eq!(rx.recv().unwrap(), 42);
+//! assert_eq!(block_on(async { 1 + 1 }), 2);
+//! ```
+
+pub use crate::{
+    block_on,
+    local::{LocalPool, LocalSpawner, SpawnError},
+    pool::{ThreadPool, ThreadPoolBuilder},
+};
+#[cfg(feature = "stream")]
+pub use crate::stream::{block_on_stream, BlockingStream};
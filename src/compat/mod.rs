@@ -0,0 +1,3 @@
+//! Drop-in replacements for other crates' executor APIs, backed by pollster.
+
+pub mod futures_executor;
@@ -0,0 +1,64 @@
+// Cooperative scheduling budget. An executor gives each poll of a task a budget; futures that can
+// keep completing without ever waiting spend it, and are made to yield once it runs out so the
+// task can't starve everything else on its thread. Outside of a budgeted poll nothing is limited.
+
+use std::{
+    cell::Cell,
+    future::poll_fn,
+    task::{Context, Poll},
+};
+
+thread_local! {
+    static BUDGET: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+/// Run `f` with `budget` operations available to the futures it polls.
+pub(crate) fn budgeted<R>(budget: Option<u32>, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<u32>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            BUDGET.with(|cell| cell.set(self.0));
+        }
+    }
+
+    let _restore = Restore(BUDGET.with(|cell| cell.replace(budget)));
+    f()
+}
+
+/// Spend one unit of the budget, or wake the task and return `Pending` if it is exhausted.
+pub(crate) fn poll_proceed(cx: &mut Context<'_>) -> Poll<()> {
+    BUDGET.with(|cell| match cell.get() {
+        None => Poll::Ready(()),
+        Some(0) => {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+        Some(left) => {
+            cell.set(Some(left - 1));
+            Poll::Ready(())
+        }
+    })
+}
+
+/// Spend one unit of the current task's cooperative budget, yielding first if it has run out.
+///
+/// Long loops over work that never has to wait can call this to let an executor with a budget,
+/// such as [`LocalPool`](crate::local::LocalPool), run other tasks in between. Outside of such an
+/// executor it completes immediately.
+///
+/// # Example
+///
+/// ```
+/// let mut sum = 0;
+/// pollster::block_on(async {
+///     for i in 0..1000 {
+///         pollster::task::consume_budget().await;
+///         sum += i;
+///     }
+/// });
+/// assert_eq!(sum, 499500);
+/// ```
+pub async fn consume_budget() {
+    poll_fn(poll_proceed).await
+}
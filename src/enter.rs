@@ -0,0 +1,73 @@
+// Tracks whether the current thread is in the middle of polling tasks that share it, where a
+// blocking call would stop the others from making progress.
+
+use std::cell::Cell;
+
+thread_local! {
+    static CONTEXT: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+/// Run `f` marked as polling on behalf of `context`, which names the executor in panic messages.
+pub(crate) fn entered<R>(context: &'static str, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<&'static str>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CONTEXT.with(|cell| cell.set(self.0));
+        }
+    }
+
+    let _restore = Restore(CONTEXT.with(|cell| cell.replace(Some(context))));
+    f()
+}
+
+/// Whether blocking the current thread is safe, as far as this crate can tell.
+///
+/// This returns `false` while the thread is polling tasks of a [`LocalPool`](crate::local::LocalPool),
+/// since every other task on the pool is stuck until a blocking call returns. See
+/// [`assert_blocking_allowed`] for the panicking version.
+///
+/// # Example
+///
+/// ```
+/// let mut pool = pollster::local::LocalPool::new();
+/// assert!(pollster::is_blocking_allowed());
+/// pool.spawner().spawn_local(async { assert!(!pollster::is_blocking_allowed()) }).unwrap();
+/// pool.run();
+/// ```
+pub fn is_blocking_allowed() -> bool {
+    CONTEXT.with(Cell::get).is_none()
+}
+
+/// Panic if blocking the current thread could deadlock.
+///
+/// Libraries with synchronous wrappers around async code can call this (or the
+/// [`assert_blocking_allowed!`](crate::assert_blocking_allowed!) macro) before blocking, to turn a
+/// silent hang into a panic pointing at the caller. It checks the same thing as
+/// [`is_blocking_allowed`]; other runtimes' threads are not detected.
+///
+/// # Panics
+///
+/// Panics when called from inside a task of a [`LocalPool`](crate::local::LocalPool).
+///
+/// # Example
+///
+/// ```
+/// fn read_config() -> String {
+///     pollster::assert_blocking_allowed();
+///     pollster::block_on(async { String::from("debug = true") })
+/// }
+///
+/// assert_eq!(read_config(), "debug = true");
+/// ```
+#[track_caller]
+pub fn assert_blocking_allowed() {
+    if let Some(context) = CONTEXT.with(Cell::get) {
+        panic!(
+            "blocking call made from inside {}, which would stop every other task on this thread \
+             until it returns and can deadlock; `.await` the future instead, or move the blocking \
+             work onto another thread with `pollster::unblock`",
+            context,
+        );
+    }
+}
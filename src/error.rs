@@ -0,0 +1,89 @@
+use crate::{future::Aborted, local::SpawnError, time::Elapsed};
+use std::{fmt, io};
+
+/// One error type covering the ways the crate's fallible operations can fail.
+///
+/// Each operation reports its own specific error, like [`Elapsed`] or [`Aborted`]; all of them
+/// convert into this with `?`, so code wrapping several of them has a single type to match on and
+/// propagate. It converts into [`io::Error`] in turn.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// fn fetch() -> Result<u32, pollster::Error> {
+///     let (request, _handle) = pollster::future::abortable(std::future::pending());
+///     let response = pollster::block_on(pollster::time::timeout(Duration::from_millis(1), request))??;
+///     Ok(response)
+/// }
+///
+/// assert_eq!(fetch(), Err(pollster::Error::Timeout));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Error {
+    /// A deadline passed first. Converted from [`Elapsed`], unless the timer was shut down.
+    Timeout,
+    /// The operation was aborted. Converted from [`Aborted`].
+    Cancelled,
+    /// The executor was dropped, or [`shutdown`](crate::shutdown) stopped the timer thread.
+    /// Converted from a [`SpawnError`] for a dropped executor and from an [`Elapsed`] that was cut
+    /// short.
+    ExecutorShutdown,
+    /// The executor can't do what was asked, like spawning on a [`Runner`](crate::Runner).
+    /// Converted from a [`SpawnError`] for which [`is_unsupported`](SpawnError::is_unsupported)
+    /// holds.
+    Unsupported,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Error::Timeout => "deadline has elapsed",
+            Error::Cancelled => "operation was cancelled",
+            Error::ExecutorShutdown => "executor has been shut down",
+            Error::Unsupported => "operation isn't supported by the executor",
+        })
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Elapsed> for Error {
+    fn from(elapsed: Elapsed) -> Self {
+        if elapsed.is_shut_down() {
+            Error::ExecutorShutdown
+        } else {
+            Error::Timeout
+        }
+    }
+}
+
+impl From<Aborted> for Error {
+    fn from(_: Aborted) -> Self {
+        Error::Cancelled
+    }
+}
+
+impl From<SpawnError> for Error {
+    fn from(err: SpawnError) -> Self {
+        if err.is_unsupported() {
+            Error::Unsupported
+        } else {
+            Error::ExecutorShutdown
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        let kind = match err {
+            Error::Timeout => io::ErrorKind::TimedOut,
+            Error::Cancelled => io::ErrorKind::Interrupted,
+            Error::ExecutorShutdown => io::ErrorKind::Other,
+            Error::Unsupported => io::ErrorKind::Unsupported,
+        };
+        io::Error::new(kind, err)
+    }
+}
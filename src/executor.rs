@@ -0,0 +1,447 @@
+use crate::{local::SpawnError, pool::JoinHandle};
+use std::{
+    any::Any,
+    cell::Cell,
+    fmt,
+    future::Future,
+    panic::{self, AssertUnwindSafe, Location},
+    pin::Pin,
+    process,
+    sync::{Arc, Mutex, PoisonError, RwLock},
+    task::Poll,
+    thread,
+    time::Duration,
+};
+
+pub(crate) type SendFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+// An executor that accepts futures from any thread.
+pub(crate) trait Spawn: Send + Sync {
+    fn spawn(&self, fut: SendFuture, location: &'static Location<'static>) -> Result<(), SpawnError>;
+}
+
+/// An executor that can block the current thread until a future completes.
+///
+/// Accept `&impl Executor` instead of calling [`block_on`](crate::block_on) directly to let callers
+/// (and tests) decide how futures get driven.
+///
+/// # Example
+///
+/// ```
+/// use pollster::{local::LocalPool, pool::ThreadPool, Executor, Runner};
+///
+/// fn load_config(executor: &impl Executor) -> String {
+///     let name = match executor.spawn(async { "config" }) {
+///         Ok(handle) => executor.block_on(handle),
+///         Err(err) if err.is_unsupported() => "config",
+///         Err(err) => panic!("{}", err),
+///     };
+///     executor.block_on(async move { name.to_string() })
+/// }
+///
+/// assert_eq!(load_config(&Runner::new()), "config");
+/// let pool = ThreadPool::new().unwrap();
+/// assert_eq!(load_config(&pool), "config");
+/// assert_eq!(load_config(&pool.handle()), "config");
+/// assert_eq!(load_config(&LocalPool::new()), "config");
+/// ```
+pub trait Executor {
+    /// Block the current thread until `fut` is ready.
+    fn block_on<F: Future>(&self, fut: F) -> F::Output;
+
+    /// Spawn `fut` to run in the background, returning a handle that resolves to its output.
+    ///
+    /// Executors that only block the current thread, like [`Runner`], fail with a [`SpawnError`]
+    /// for which [`is_unsupported`](SpawnError::is_unsupported) holds; that's the default.
+    #[track_caller]
+    fn spawn<F>(&self, fut: F) -> Result<JoinHandle<F::Output>, SpawnError>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        drop(fut);
+        Err(SpawnError::unsupported())
+    }
+}
+
+type IdleCallback = Arc<Mutex<dyn FnMut(IdleContext) -> IdleDecision + Send>>;
+
+/// The default [`Executor`], which drives futures on the current thread with
+/// [`block_on`](crate::block_on).
+#[derive(Default, Clone)]
+pub struct Runner {
+    on_idle: Option<IdleCallback>,
+}
+
+impl Runner {
+    /// Create a runner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `f` whenever the future is pending, before the thread would park, and let it decide
+    /// what to do next.
+    ///
+    /// This lets a game or GUI loop keep ticking while a future completes: the callback can run a
+    /// frame of its own work, then ask to poll again straight away, to park until woken for at
+    /// most some time, or to park until woken. Timed parks don't pump the macOS run loop or the
+    /// Windows message queue, since the callback is driving the thread's own loop.
+    ///
+    /// Clones of the runner share the callback. A `block_on` nested inside another on the same
+    /// runner runs without it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use pollster::{Executor, IdleDecision, Runner};
+    ///
+    /// let mut frames = 0;
+    /// let runner = Runner::new().on_idle(move |_| {
+    ///     frames += 1; // render a frame, say
+    ///     IdleDecision::ParkTimeout(Duration::from_millis(1))
+    /// });
+    /// runner.block_on(pollster::time::sleep(Duration::from_millis(20)));
+    ///
+    /// let patient = Runner::new().on_idle(|_| IdleDecision::ParkTimeout(Duration::MAX));
+    /// patient.block_on(pollster::time::sleep(Duration::from_millis(1)));
+    /// ```
+    pub fn on_idle(mut self, f: impl FnMut(IdleContext) -> IdleDecision + Send + 'static) -> Self {
+        self.on_idle = Some(Arc::new(Mutex::new(f)));
+        self
+    }
+}
+
+impl Executor for Runner {
+    #[track_caller]
+    fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        match self.on_idle.as_ref().and_then(|on_idle| on_idle.try_lock().ok()) {
+            Some(mut on_idle) => crate::block_on_inner(fut, None, crate::Signal::current, Some(&mut *on_idle)),
+            None => crate::block_on(fut),
+        }
+    }
+}
+
+impl fmt::Debug for Runner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Runner")
+            .field("on_idle", &self.on_idle.is_some())
+            .finish()
+    }
+}
+
+/// What a [`Runner::on_idle`] callback is told about the blocked future.
+#[derive(Debug, Clone, Copy)]
+pub struct IdleContext {
+    pub(crate) polls: u64,
+    pub(crate) elapsed: Duration,
+}
+
+impl IdleContext {
+    /// How many times the future has been polled so far.
+    pub fn polls(&self) -> u64 {
+        self.polls
+    }
+
+    /// How long the `block_on` call has been running.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+/// What a [`Runner::on_idle`] callback wants done next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleDecision {
+    /// Poll the future again straight away, even if it hasn't been woken.
+    Poll,
+    /// Park until the future is woken, or until the timeout passes, then poll it again. A timeout
+    /// too long to represent, like [`Duration::MAX`], parks until woken.
+    ParkTimeout(Duration),
+    /// Park until the future is woken, as `block_on` does without a callback.
+    Park,
+}
+
+/// A cloneable, thread-safe handle for spawning futures onto a [`ThreadPool`] or [`LocalPool`],
+/// returned by [`ThreadPool::handle`] and [`LocalPool::handle`].
+///
+/// A handle can be stashed wherever only a plain context is available, such as a GUI or C
+/// callback running on a thread of its own. It doesn't keep its executor alive: once the executor
+/// is dropped, spawning fails with a [`SpawnError`].
+///
+/// [`ThreadPool`]: crate::pool::ThreadPool
+/// [`ThreadPool::handle`]: crate::pool::ThreadPool::handle
+/// [`LocalPool`]: crate::local::LocalPool
+/// [`LocalPool::handle`]: crate::local::LocalPool::handle
+///
+/// # Example
+///
+/// ```
+/// use pollster::pool::ThreadPool;
+///
+/// let pool = ThreadPool::new().unwrap();
+/// let handle = pool.handle();
+/// // Some callback, called from a thread that knows nothing about the pool.
+/// let callback = move |x: u32| handle.block_on(async move { x * 2 });
+/// assert_eq!(std::thread::spawn(move || callback(21)).join().unwrap(), 42);
+/// ```
+#[derive(Clone)]
+pub struct Handle {
+    spawner: Arc<dyn Spawn>,
+}
+
+impl Handle {
+    pub(crate) fn new(spawner: Arc<dyn Spawn>) -> Self {
+        Self { spawner }
+    }
+
+    /// Spawn a future onto the executor, returning a handle that resolves to its output.
+    ///
+    /// If the future panics, what happens depends on the executor's [`PanicPolicy`]; by default
+    /// the panic is resumed when the handle is awaited. Fails if the executor has been dropped.
+    #[track_caller]
+    pub fn spawn<F>(&self, fut: F) -> Result<JoinHandle<F::Output>, SpawnError>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (task, handle) = JoinHandle::wrap(fut);
+        self.spawner.spawn(Box::pin(task), Location::caller())?;
+        Ok(handle)
+    }
+
+    /// Spawn a future onto the executor and block the current thread until it completes.
+    ///
+    /// Don't call this from a thread the executor needs to make progress: on the thread running a
+    /// `LocalPool`, or from a worker of a `ThreadPool` with a single worker, it never returns.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the executor has been dropped, or if the future panics.
+    #[track_caller]
+    pub fn block_on<F>(&self, fut: F) -> F::Output
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        match self.spawn(fut) {
+            Ok(handle) => crate::block_on(handle),
+            Err(err) => panic!("cannot block on a future through a handle: {}", err),
+        }
+    }
+}
+
+// The future given to `block_on` needn't be `Send`, so it's driven on the current thread rather
+// than spawned like `Handle::block_on` does.
+impl Executor for Handle {
+    #[track_caller]
+    fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        crate::block_on(fut)
+    }
+
+    #[track_caller]
+    fn spawn<F>(&self, fut: F) -> Result<JoinHandle<F::Output>, SpawnError>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        Handle::spawn(self, fut)
+    }
+}
+
+impl fmt::Debug for Handle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handle").finish_non_exhaustive()
+    }
+}
+
+/// What an executor does when one of its tasks panics, set with
+/// [`ThreadPoolBuilder::on_task_panic`] or [`LocalPool::set_on_task_panic`].
+///
+/// Whatever the policy, the standard panic hook has already reported the panic by then, and the
+/// hook set with [`set_task_panic_hook`] is called before the policy is applied.
+///
+/// [`ThreadPoolBuilder::on_task_panic`]: crate::pool::ThreadPoolBuilder::on_task_panic
+/// [`LocalPool::set_on_task_panic`]: crate::local::LocalPool::set_on_task_panic
+///
+/// # Example
+///
+/// ```
+/// use std::{cell::Cell, rc::Rc};
+/// use pollster::{local::LocalPool, PanicPolicy};
+///
+/// let mut pool = LocalPool::new();
+/// pool.set_on_task_panic(PanicPolicy::LogAndContinue);
+/// let finished = Rc::new(Cell::new(false));
+/// pool.spawner().spawn_local(async { panic!("background task failed") }).unwrap();
+/// pool.spawner().spawn_local({
+///     let finished = Rc::clone(&finished);
+///     async move { finished.set(true) }
+/// }).unwrap();
+/// pool.run();
+/// assert!(finished.get());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum PanicPolicy {
+    /// Resume the panic where the task's [`JoinHandle`] is awaited, or hand its payload to the
+    /// [`JoinError`](crate::pool::JoinError) of [`JoinHandle::try_join`]. For a task spawned
+    /// without a handle, the panic just ends the task on a [`ThreadPool`](crate::pool::ThreadPool),
+    /// and unwinds out of the call running a [`LocalPool`](crate::local::LocalPool).
+    #[default]
+    Propagate,
+    /// Print the task's name and panic message to stderr, then drop the task and keep running the
+    /// others. Awaiting the task's [`JoinHandle`] still panics, since there is no output to give;
+    /// [`JoinHandle::try_join`] fails with a [`JoinError`](crate::pool::JoinError) instead.
+    LogAndContinue,
+    /// Print the task's name and panic message to stderr, then abort the process.
+    AbortProcess,
+}
+
+type TaskPanicHook = Arc<dyn Fn(Option<&str>, &(dyn Any + Send)) + Send + Sync>;
+
+static TASK_PANIC_HOOK: RwLock<Option<TaskPanicHook>> = RwLock::new(None);
+
+thread_local! {
+    // The panic policy and name of the task being polled on this thread.
+    static TASK: Cell<(PanicPolicy, Option<&'static str>)> = const { Cell::new((PanicPolicy::Propagate, None)) };
+}
+
+/// Set a hook that is called with the task's name, if it was given one, and the panic payload
+/// whenever a task spawned on a [`ThreadPool`](crate::pool::ThreadPool) or
+/// [`LocalPool`](crate::local::LocalPool) panics. It replaces any hook set before.
+///
+/// The hook runs on the thread that polled the task, before the executor's [`PanicPolicy`] is
+/// applied, so it is the place to report the panic to a crash or metrics service.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use pollster::pool::ThreadPool;
+///
+/// let panicked = Arc::new(Mutex::new(Vec::new()));
+/// pollster::set_task_panic_hook({
+///     let panicked = Arc::clone(&panicked);
+///     move |name, _payload| panicked.lock().unwrap().push(name.map(str::to_owned))
+/// });
+///
+/// let pool = ThreadPool::new().unwrap();
+/// let handle = pool.spawn_named("upload", async { panic!("connection reset") });
+/// assert!(std::panic::catch_unwind(|| pollster::block_on(handle)).is_err());
+/// assert_eq!(*panicked.lock().unwrap(), [Some("upload".to_owned())]);
+/// ```
+pub fn set_task_panic_hook(hook: impl Fn(Option<&str>, &(dyn Any + Send)) + Send + Sync + 'static) {
+    *TASK_PANIC_HOOK.write().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(hook));
+}
+
+// Poll one of an executor's tasks, applying `policy` if it panics. Returns the panic if it should
+// propagate; a task that panicked and was dealt with counts as finished.
+pub(crate) fn poll_task(
+    policy: PanicPolicy,
+    name: Option<&'static str>,
+    poll: impl FnOnce() -> Poll<()>,
+) -> thread::Result<Poll<()>> {
+    struct Restore((PanicPolicy, Option<&'static str>));
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            TASK.with(|cell| cell.set(self.0));
+        }
+    }
+
+    let _restore = Restore(TASK.with(|cell| cell.replace((policy, name))));
+    match panic::catch_unwind(AssertUnwindSafe(poll)) {
+        Ok(poll) => Ok(poll),
+        Err(payload) => task_panicked(payload).map_or(Ok(Poll::Ready(())), Err),
+    }
+}
+
+// Report the panic of the task being polled to the hook and apply its executor's policy, giving the
+// payload back if it should propagate.
+pub(crate) fn task_panicked(payload: Box<dyn Any + Send>) -> Option<Box<dyn Any + Send>> {
+    let (policy, name) = TASK.with(Cell::get);
+    // Cloned out so that the hook may replace itself.
+    let hook = TASK_PANIC_HOOK.read().unwrap_or_else(PoisonError::into_inner).clone();
+    if let Some(hook) = hook {
+        hook(name, &*payload);
+    }
+
+    let describe = || {
+        let message = match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+            (Some(message), _) => message,
+            (_, Some(message)) => message.as_str(),
+            _ => "Box<dyn Any>",
+        };
+        match name {
+            Some(name) => format!("task '{}' panicked: {}", name, message),
+            None => format!("task panicked: {}", message),
+        }
+    };
+    match policy {
+        PanicPolicy::Propagate => Some(payload),
+        PanicPolicy::LogAndContinue => {
+            eprintln!("pollster: {}; continuing", describe());
+            None
+        }
+        PanicPolicy::AbortProcess => {
+            eprintln!("pollster: {}; aborting", describe());
+            process::abort()
+        }
+    }
+}
+
+/// A snapshot of one live task, as listed by [`ThreadPool::dump`] and [`LocalPool::dump`].
+///
+/// [`ThreadPool::dump`]: crate::pool::ThreadPool::dump
+/// [`LocalPool::dump`]: crate::local::LocalPool::dump
+#[derive(Debug, Clone)]
+pub struct TaskInfo {
+    pub(crate) name: Option<&'static str>,
+    pub(crate) location: &'static Location<'static>,
+    pub(crate) state: TaskState,
+    pub(crate) polls: u64,
+}
+
+impl TaskInfo {
+    /// The name the task was spawned with, if any.
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    /// Where the task was spawned.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    /// What the task was doing when the snapshot was taken.
+    pub fn state(&self) -> TaskState {
+        self.state
+    }
+
+    /// How many times the task has been polled.
+    pub fn polls(&self) -> u64 {
+        self.polls
+    }
+}
+
+impl fmt::Display for TaskInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name {
+            Some(name) => write!(f, "task '{}'", name)?,
+            None => f.write_str("unnamed task")?,
+        }
+        write!(f, " spawned at {}: {:?}, polled {} time(s)", self.location, self.state, self.polls)
+    }
+}
+
+/// What a task was doing when a [`TaskInfo`] snapshot was taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TaskState {
+    /// Being polled.
+    Running,
+    /// Woken, or newly spawned, and waiting for its turn to be polled.
+    Scheduled,
+    /// Waiting to be woken.
+    Waiting,
+}
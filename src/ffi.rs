@@ -0,0 +1,188 @@
+//! Bridging completion callbacks from foreign code into futures.
+//!
+//! C APIs usually report that an operation finished by calling a function pointer with a
+//! user-supplied context pointer, often from a thread of their own. [`completion`] pairs a
+//! [`Completer`], which is passed through the API as that context pointer, with a [`Completion`]
+//! future to block on. The foreign code only ever sees an opaque pointer and a plain
+//! `extern "C"` function; waking the blocked thread happens on the Rust side.
+
+use crate::oneshot;
+use std::{
+    error::Error,
+    ffi::{c_int, c_void},
+    fmt,
+    future::Future,
+    mem::{self, ManuallyDrop},
+    pin::Pin,
+    ptr::{self, NonNull},
+    task::{Context, Poll},
+};
+
+type Sender<T> = oneshot::Sender<Result<T, c_int>>;
+
+/// Create a [`Completer`] and the [`Completion`] future it resolves.
+///
+/// # Example
+///
+/// ```
+/// use std::ffi::{c_int, c_void};
+/// use pollster::ffi::{Completer, CompletionError};
+///
+/// # struct Context(*mut c_void);
+/// # unsafe impl Send for Context {}
+/// // Stands in for a C function that reports its result from another thread.
+/// unsafe extern "C" fn read_sensor(
+///     callback: unsafe extern "C" fn(*mut c_void, c_int, *const u32),
+///     context: *mut c_void,
+/// ) {
+///     let context = Context(context);
+///     std::thread::spawn(move || {
+///         let context = context;
+///         let reading: u32 = 42;
+///         unsafe { callback(context.0, 0, &reading) };
+///     });
+/// }
+///
+/// let (completer, completion) = pollster::ffi::completion::<u32>();
+/// unsafe { read_sensor(Completer::<u32>::CALLBACK, completer.into_raw()) };
+/// assert_eq!(pollster::block_on(completion), Ok(42));
+///
+/// let (completer, completion) = pollster::ffi::completion::<u32>();
+/// completer.complete(Err(-5));
+/// assert_eq!(pollster::block_on(completion), Err(CompletionError::Failed(-5)));
+/// ```
+pub fn completion<T>() -> (Completer<T>, Completion<T>) {
+    let (tx, rx) = oneshot::channel();
+    let completer = Completer {
+        sender: NonNull::from(Box::leak(Box::new(tx))),
+    };
+    (completer, Completion { rx })
+}
+
+/// The completing half of a [`completion`], which can be handed to foreign code as a single
+/// pointer.
+///
+/// Completing it, from any thread, resolves the paired [`Completion`]. Dropping it without
+/// completing resolves it to [`CompletionError::Dropped`].
+#[repr(transparent)]
+pub struct Completer<T> {
+    sender: NonNull<Sender<T>>,
+}
+
+// SAFETY: the completer owns its sender, which only hands `T` over to the receiving thread.
+unsafe impl<T: Send> Send for Completer<T> {}
+
+impl<T> Completer<T> {
+    /// Resolve the paired [`Completion`] with a value, or with an error code.
+    pub fn complete(self, result: Result<T, c_int>) {
+        self.into_sender().send(result);
+    }
+
+    /// Turn the completer into an opaque pointer, to pass as a callback's context.
+    ///
+    /// The pointer must eventually be passed to [`from_raw`](Completer::from_raw) or to
+    /// [`CALLBACK`](Completer::CALLBACK), exactly once, or the completion never resolves.
+    pub fn into_raw(self) -> *mut c_void {
+        ManuallyDrop::new(self).sender.as_ptr().cast()
+    }
+
+    /// Take back a completer turned into a pointer by [`into_raw`](Completer::into_raw).
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from `into_raw` on a `Completer` of the same `T`, and must not have been
+    /// taken back already.
+    pub unsafe fn from_raw(ptr: *mut c_void) -> Self {
+        Self {
+            sender: NonNull::new_unchecked(ptr.cast()),
+        }
+    }
+
+    fn into_sender(self) -> Sender<T> {
+        // SAFETY: the sender was leaked from a box in `completion`, and the completer is consumed.
+        *unsafe { Box::from_raw(ManuallyDrop::new(self).sender.as_ptr()) }
+    }
+}
+
+impl<T: Copy> Completer<T> {
+    /// A callback for foreign code to complete a completer passed as its context pointer.
+    ///
+    /// It is called as `callback(context, status, value)`. A `status` of 0 means success, and
+    /// `value` is copied out; any other status is reported as [`CompletionError::Failed`] and
+    /// `value` is ignored.
+    ///
+    /// # Safety
+    ///
+    /// The callback must be called exactly once per context pointer, with the context produced
+    /// by [`into_raw`](Completer::into_raw) on a `Completer<T>`. On success, `value` must point
+    /// to a valid `T` for the duration of the call.
+    pub const CALLBACK: unsafe extern "C" fn(*mut c_void, c_int, *const T) = complete_raw::<T>;
+}
+
+unsafe extern "C" fn complete_raw<T: Copy>(context: *mut c_void, status: c_int, value: *const T) {
+    let completer = Completer::<T>::from_raw(context);
+    if status != 0 {
+        completer.complete(Err(status));
+    } else if mem::size_of::<T>() == 0 {
+        // Zero-sized values need no storage, so allow callers to pass null for them.
+        completer.complete(Ok(ptr::read(NonNull::<T>::dangling().as_ptr())));
+    } else {
+        completer.complete(Ok(ptr::read(value)));
+    }
+}
+
+impl<T> Drop for Completer<T> {
+    fn drop(&mut self) {
+        // SAFETY: as in `into_sender`; dropping the sender resolves the completion as dropped.
+        drop(unsafe { Box::from_raw(self.sender.as_ptr()) });
+    }
+}
+
+impl<T> fmt::Debug for Completer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Completer").finish_non_exhaustive()
+    }
+}
+
+/// Future returned by [`completion`], resolving once the paired [`Completer`] completes.
+pub struct Completion<T> {
+    rx: oneshot::Receiver<Result<T, c_int>>,
+}
+
+impl<T> Future for Completion<T> {
+    type Output = Result<T, CompletionError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.rx).poll(cx).map(|result| match result {
+            Some(Ok(value)) => Ok(value),
+            Some(Err(code)) => Err(CompletionError::Failed(code)),
+            None => Err(CompletionError::Dropped),
+        })
+    }
+}
+
+impl<T> fmt::Debug for Completion<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Completion").finish_non_exhaustive()
+    }
+}
+
+/// Error returned by a [`Completion`] that did not succeed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionError {
+    /// The operation reported this error code.
+    Failed(c_int),
+    /// The [`Completer`] was dropped without completing.
+    Dropped,
+}
+
+impl fmt::Display for CompletionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompletionError::Failed(code) => write!(f, "operation failed with error code {}", code),
+            CompletionError::Dropped => f.write_str("completer was dropped without completing"),
+        }
+    }
+}
+
+impl Error for CompletionError {}
@@ -0,0 +1,47 @@
+//! Asynchronous filesystem operations, run on the [`unblock`](crate::unblock) pool.
+
+use crate::unblock;
+use std::{
+    fs::{self, Metadata},
+    io,
+    path::Path,
+};
+
+/// Read the entire contents of a file, like [`std::fs::read`].
+///
+/// # Example
+///
+/// ```
+/// # let dir = std::env::temp_dir().join("pollster-fs-doc");
+/// # std::fs::create_dir_all(&dir).unwrap();
+/// let path = dir.join("greeting.txt");
+/// pollster::block_on(async {
+///     pollster::fs::write(&path, "hello").await?;
+///     assert_eq!(pollster::fs::read(&path).await?, b"hello");
+///     std::io::Result::Ok(())
+/// })
+/// .unwrap();
+/// ```
+pub async fn read(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+    let path = path.as_ref().to_owned();
+    unblock(move || fs::read(path)).await
+}
+
+/// Read the entire contents of a file into a string, like [`std::fs::read_to_string`].
+pub async fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+    let path = path.as_ref().to_owned();
+    unblock(move || fs::read_to_string(path)).await
+}
+
+/// Write a slice as the entire contents of a file, like [`std::fs::write`].
+pub async fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = path.as_ref().to_owned();
+    let contents = contents.as_ref().to_owned();
+    unblock(move || fs::write(path, contents)).await
+}
+
+/// Query the metadata of a file or directory, like [`std::fs::metadata`].
+pub async fn metadata(path: impl AsRef<Path>) -> io::Result<Metadata> {
+    let path = path.as_ref().to_owned();
+    unblock(move || fs::metadata(path)).await
+}
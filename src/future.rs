@@ -0,0 +1,312 @@
+//! Combinators for arbitrary futures, and the types returned when racing them.
+
+use std::{
+    error::Error,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+};
+
+struct AbortInner {
+    aborted: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
+
+/// Wrap a future so that it can be aborted through the returned [`AbortHandle`].
+///
+/// Once aborted, the future is no longer polled and resolves to `Err(Aborted)`. Aborting wakes
+/// the task, so a thread parked in [`block_on`](crate::block_on) returns promptly.
+///
+/// # Example
+///
+/// ```
+/// use std::{thread, time::Duration};
+///
+/// let (fut, handle) = pollster::future::abortable(std::future::pending::<()>());
+/// thread::spawn(move || {
+///     thread::sleep(Duration::from_millis(10));
+///     handle.abort();
+/// });
+/// assert_eq!(pollster::block_on(fut), Err(pollster::future::Aborted));
+/// ```
+pub fn abortable<F: Future>(fut: F) -> (Abortable<F>, AbortHandle) {
+    let inner = Arc::new(AbortInner {
+        aborted: AtomicBool::new(false),
+        waker: Mutex::new(None),
+    });
+    let handle = AbortHandle {
+        inner: Arc::clone(&inner),
+    };
+    (Abortable { fut, inner }, handle)
+}
+
+/// Future returned by [`abortable`].
+pub struct Abortable<F> {
+    fut: F,
+    inner: Arc<AbortInner>,
+}
+
+impl<F> Abortable<F> {
+    /// Whether the future has been aborted.
+    pub fn is_aborted(&self) -> bool {
+        self.inner.aborted.load(Ordering::Acquire)
+    }
+}
+
+impl<F: Future> Future for Abortable<F> {
+    type Output = Result<F::Output, Aborted>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `fut` is never moved out of `self`.
+        let this = unsafe { self.get_unchecked_mut() };
+        if this.is_aborted() {
+            return Poll::Ready(Err(Aborted));
+        }
+
+        {
+            let mut waker = this.inner.waker.lock().unwrap();
+            match &mut *waker {
+                Some(waker) if waker.will_wake(cx.waker()) => {}
+                waker => *waker = Some(cx.waker().clone()),
+            }
+        }
+        // An abort between the first check and storing the waker would otherwise go unnoticed.
+        if this.is_aborted() {
+            return Poll::Ready(Err(Aborted));
+        }
+
+        unsafe { Pin::new_unchecked(&mut this.fut) }.poll(cx).map(Ok)
+    }
+}
+
+impl<F> fmt::Debug for Abortable<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Abortable")
+            .field("aborted", &self.is_aborted())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Aborts an [`Abortable`] future, from any thread.
+#[derive(Clone)]
+pub struct AbortHandle {
+    inner: Arc<AbortInner>,
+}
+
+impl AbortHandle {
+    /// Abort the future. It resolves to `Err(Aborted)` the next time it is polled, and is woken so
+    /// that happens soon.
+    pub fn abort(&self) {
+        self.inner.aborted.store(true, Ordering::Release);
+        let waker = self.inner.waker.lock().unwrap().take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Whether [`abort`](Self::abort) has been called.
+    pub fn is_aborted(&self) -> bool {
+        self.inner.aborted.load(Ordering::Acquire)
+    }
+}
+
+impl fmt::Debug for AbortHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AbortHandle")
+            .field("aborted", &self.is_aborted())
+            .finish()
+    }
+}
+
+/// Returned by an [`Abortable`] future that was aborted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Aborted;
+
+impl fmt::Display for Aborted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("future was aborted")
+    }
+}
+
+impl Error for Aborted {}
+
+/// One of two values, such as the output of whichever of two raced futures finished first.
+///
+/// If both sides are futures with the same output, `Either` is itself a future.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Either<A, B> {
+    /// The first value.
+    Left(A),
+    /// The second value.
+    Right(B),
+}
+
+impl<A, B> Either<A, B> {
+    /// Whether this is a `Left`.
+    pub fn is_left(&self) -> bool {
+        matches!(self, Either::Left(_))
+    }
+
+    /// Whether this is a `Right`.
+    pub fn is_right(&self) -> bool {
+        matches!(self, Either::Right(_))
+    }
+
+    /// The `Left` value, if any.
+    pub fn left(self) -> Option<A> {
+        match self {
+            Either::Left(a) => Some(a),
+            Either::Right(_) => None,
+        }
+    }
+
+    /// The `Right` value, if any.
+    pub fn right(self) -> Option<B> {
+        match self {
+            Either::Left(_) => None,
+            Either::Right(b) => Some(b),
+        }
+    }
+
+    /// Borrow the value inside.
+    pub fn as_ref(&self) -> Either<&A, &B> {
+        match self {
+            Either::Left(a) => Either::Left(a),
+            Either::Right(b) => Either::Right(b),
+        }
+    }
+
+    /// Apply `f` to a `Left` value.
+    pub fn map_left<C>(self, f: impl FnOnce(A) -> C) -> Either<C, B> {
+        match self {
+            Either::Left(a) => Either::Left(f(a)),
+            Either::Right(b) => Either::Right(b),
+        }
+    }
+
+    /// Apply `f` to a `Right` value.
+    pub fn map_right<C>(self, f: impl FnOnce(B) -> C) -> Either<A, C> {
+        match self {
+            Either::Left(a) => Either::Left(a),
+            Either::Right(b) => Either::Right(f(b)),
+        }
+    }
+
+    /// Swap `Left` and `Right`.
+    pub fn flip(self) -> Either<B, A> {
+        match self {
+            Either::Left(a) => Either::Right(a),
+            Either::Right(b) => Either::Left(b),
+        }
+    }
+}
+
+impl<T> Either<T, T> {
+    /// The value inside, whichever side it is on.
+    pub fn into_inner(self) -> T {
+        match self {
+            Either::Left(value) | Either::Right(value) => value,
+        }
+    }
+}
+
+impl<T, A, B> Either<(T, A), (T, B)> {
+    /// Pull the common first element out of both sides.
+    ///
+    /// This turns the result of racing two futures with the same output into that output and
+    /// whichever future is left.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use pollster::future::Either;
+    ///
+    /// let raced: Either<(u32, &str), (u32, char)> = Either::Right((7, 'x'));
+    /// assert_eq!(raced.factor_first(), (7, Either::Right('x')));
+    /// ```
+    pub fn factor_first(self) -> (T, Either<A, B>) {
+        match self {
+            Either::Left((value, a)) => (value, Either::Left(a)),
+            Either::Right((value, b)) => (value, Either::Right(b)),
+        }
+    }
+}
+
+impl<A, B, T> Either<(A, T), (B, T)> {
+    /// Pull the common second element out of both sides.
+    pub fn factor_second(self) -> (Either<A, B>, T) {
+        match self {
+            Either::Left((a, value)) => (Either::Left(a), value),
+            Either::Right((b, value)) => (Either::Right(b), value),
+        }
+    }
+}
+
+impl<A, B> Future for Either<A, B>
+where
+    A: Future,
+    B: Future<Output = A::Output>,
+{
+    type Output = A::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<A::Output> {
+        // SAFETY: the variant's contents are never moved out of `self`.
+        unsafe {
+            match self.get_unchecked_mut() {
+                Either::Left(a) => Pin::new_unchecked(a).poll(cx),
+                Either::Right(b) => Pin::new_unchecked(b).poll(cx),
+            }
+        }
+    }
+}
+
+/// A future that lost a race and may already have been polled, returned so it can be resumed.
+///
+/// It keeps the future pinned on the heap. Awaiting or blocking on it picks up where the race
+/// left off; dropping it cancels the future.
+pub struct Remaining<F> {
+    fut: Pin<Box<F>>,
+}
+
+impl<F> Remaining<F> {
+    pub(crate) fn new(fut: Pin<Box<F>>) -> Self {
+        Self { fut }
+    }
+
+    /// Take the pinned future back out.
+    pub fn into_pin(self) -> Pin<Box<F>> {
+        self.fut
+    }
+
+    /// Take the future back out. Only `Unpin` futures can be moved once they have been polled.
+    pub fn into_inner(self) -> F
+    where
+        F: Unpin,
+    {
+        *Pin::into_inner(self.fut)
+    }
+
+    /// Borrow the pinned future.
+    pub fn as_mut(&mut self) -> Pin<&mut F> {
+        self.fut.as_mut()
+    }
+}
+
+impl<F: Future> Future for Remaining<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        self.fut.as_mut().poll(cx)
+    }
+}
+
+impl<F> fmt::Debug for Remaining<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Remaining").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,154 @@
+// The instrumented waker handed out by `block_on` when the `debug-waker` or `trace` feature is
+// enabled. It wraps the same `Arc<Signal>` as the plain waker but observes every clone, drop and
+// wake.
+
+use crate::Signal;
+use std::{
+    sync::Arc,
+    task::{RawWaker, RawWakerVTable, Waker},
+};
+#[cfg(feature = "debug-waker")]
+use crate::probe::Site;
+#[cfg(feature = "debug-waker")]
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        OnceLock,
+    },
+};
+#[cfg(feature = "trace")]
+use crate::trace::{EventKind, Origin};
+
+// More wakes than this between two polls almost certainly means a future is waking itself in a
+// loop or every registered waker is woken for every event.
+#[cfg(feature = "debug-waker")]
+const REDUNDANT_WAKES: usize = 100;
+
+/// Waker bookkeeping kept alongside a `Signal` when the `debug-waker` feature is enabled.
+#[cfg(feature = "debug-waker")]
+#[derive(Default)]
+pub(crate) struct WakerStats {
+    label: OnceLock<String>,
+    // Live wakers, including the one `block_on` itself holds.
+    live: AtomicUsize,
+    wakes_since_poll: AtomicUsize,
+    completed: AtomicBool,
+}
+
+#[cfg(feature = "debug-waker")]
+impl WakerStats {
+    pub(crate) fn set_label(&self, label: String, site: Site) {
+        let _ = self.label.set(format!("`{}` (block_on at {})", label, site));
+    }
+
+    fn report(&self, msg: fmt::Arguments) {
+        let label = self.label.get().map_or("<unknown>", String::as_str);
+        eprintln!("pollster debug-waker: {}: {}", label, msg);
+    }
+
+    fn record_wake(&self) {
+        if self.completed.load(Ordering::Relaxed) {
+            self.report(format_args!("woken after the future already completed"));
+        }
+        self.wakes_since_poll.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn before_poll(&self) {
+        let wakes = self.wakes_since_poll.swap(0, Ordering::Relaxed);
+        if wakes > REDUNDANT_WAKES {
+            self.report(format_args!("woken {} times between two polls", wakes));
+        }
+    }
+
+    pub(crate) fn complete(&self) {
+        self.completed.store(true, Ordering::Relaxed);
+        let leaked = self.live.load(Ordering::Relaxed).saturating_sub(1);
+        if leaked > 0 {
+            self.report(format_args!(
+                "{} waker clone(s) still alive after completion; they were neither woken nor dropped",
+                leaked,
+            ));
+        }
+    }
+}
+
+/// Everything the instrumented waker records about its `Signal`.
+#[derive(Default)]
+pub(crate) struct Instrument {
+    #[cfg(feature = "debug-waker")]
+    pub(crate) stats: WakerStats,
+    #[cfg(feature = "trace")]
+    pub(crate) origin: std::sync::OnceLock<Origin>,
+}
+
+impl Instrument {
+    fn on_clone(&self) {
+        #[cfg(feature = "debug-waker")]
+        self.stats.live.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "trace")]
+        self.trace(EventKind::WakerClone);
+    }
+
+    fn on_drop(&self) {
+        #[cfg(feature = "debug-waker")]
+        self.stats.live.fetch_sub(1, Ordering::Relaxed);
+        #[cfg(feature = "trace")]
+        self.trace(EventKind::WakerDrop);
+    }
+
+    fn on_wake(&self, by_ref: bool) {
+        #[cfg(feature = "debug-waker")]
+        {
+            if !by_ref {
+                self.stats.live.fetch_sub(1, Ordering::Relaxed);
+            }
+            self.stats.record_wake();
+        }
+        #[cfg(not(feature = "debug-waker"))]
+        let _ = by_ref;
+        #[cfg(feature = "trace")]
+        self.trace(EventKind::Wake);
+    }
+
+    #[cfg(feature = "trace")]
+    fn trace(&self, kind: EventKind) {
+        if let Some(origin) = self.origin.get() {
+            origin.record(kind);
+        }
+    }
+}
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop);
+
+/// Create the instrumented waker for a `block_on` call on `signal`.
+pub(crate) fn waker(signal: &Arc<Signal>) -> Waker {
+    #[cfg(feature = "debug-waker")]
+    signal.instrument.stats.live.fetch_add(1, Ordering::Relaxed);
+    let ptr = Arc::into_raw(Arc::clone(signal)).cast::<()>();
+    unsafe { Waker::from_raw(RawWaker::new(ptr, &VTABLE)) }
+}
+
+unsafe fn clone(ptr: *const ()) -> RawWaker {
+    let signal = ptr.cast::<Signal>();
+    Arc::increment_strong_count(signal);
+    (*signal).instrument.on_clone();
+    RawWaker::new(ptr, &VTABLE)
+}
+
+unsafe fn wake(ptr: *const ()) {
+    let signal = Arc::from_raw(ptr.cast::<Signal>());
+    signal.instrument.on_wake(false);
+    signal.notify();
+}
+
+unsafe fn wake_by_ref(ptr: *const ()) {
+    let signal = &*ptr.cast::<Signal>();
+    signal.instrument.on_wake(true);
+    signal.notify();
+}
+
+unsafe fn drop(ptr: *const ()) {
+    let signal = Arc::from_raw(ptr.cast::<Signal>());
+    signal.instrument.on_drop();
+}
@@ -0,0 +1,232 @@
+//! Bridges between asynchronous and blocking I/O.
+
+mod stdio;
+
+pub use stdio::{stdin, stdout, Stdin, Stdout};
+
+use futures_io::{AsyncRead, AsyncWrite};
+use std::{
+    future::poll_fn,
+    io::{self, Read, Write},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+// Block on one I/O operation, failing with `ErrorKind::TimedOut` if it takes longer than `timeout`.
+fn block_on_io<T>(
+    timeout: Option<Duration>,
+    op: impl FnMut(&mut Context<'_>) -> Poll<io::Result<T>>,
+) -> io::Result<T> {
+    match timeout {
+        Some(timeout) => crate::block_on(crate::time::timeout(timeout, poll_fn(op)))?,
+        None => crate::block_on(poll_fn(op)),
+    }
+}
+
+fn check_timeout(timeout: Option<Duration>) -> io::Result<()> {
+    if timeout == Some(Duration::ZERO) {
+        Err(io::Error::new(io::ErrorKind::InvalidInput, "cannot set a 0 duration timeout"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Adapts an [`AsyncRead`] into a blocking [`Read`] by blocking on every read.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Read;
+/// use pollster::io::BlockingReader;
+///
+/// let mut reader = BlockingReader::new(&b"hello"[..]);
+/// let mut text = String::new();
+/// reader.read_to_string(&mut text).unwrap();
+/// assert_eq!(text, "hello");
+/// ```
+#[derive(Debug)]
+pub struct BlockingReader<R> {
+    inner: R,
+    timeout: Option<Duration>,
+}
+
+impl<R> BlockingReader<R> {
+    /// Wrap an asynchronous reader.
+    pub fn new(inner: R) -> Self {
+        Self { inner, timeout: None }
+    }
+
+    /// Set how long each read may block before failing with [`io::ErrorKind::TimedOut`], like
+    /// [`TcpStream::set_read_timeout`](std::net::TcpStream::set_read_timeout). `None` means reads
+    /// block for as long as it takes.
+    ///
+    /// A timed-out read is cancelled, so it reads nothing.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] if `timeout` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{io::{ErrorKind, Read}, time::Duration};
+    /// use pollster::io::BlockingReader;
+    ///
+    /// # struct Silent;
+    /// # impl futures_io::AsyncRead for Silent {
+    /// #     fn poll_read(
+    /// #         self: std::pin::Pin<&mut Self>,
+    /// #         _: &mut std::task::Context<'_>,
+    /// #         _: &mut [u8],
+    /// #     ) -> std::task::Poll<std::io::Result<usize>> {
+    /// #         std::task::Poll::Pending
+    /// #     }
+    /// # }
+    /// // `Silent` never has any data to read.
+    /// let mut reader = BlockingReader::new(Silent);
+    /// reader.set_read_timeout(Some(Duration::from_millis(10))).unwrap();
+    /// let err = reader.read(&mut [0; 16]).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::TimedOut);
+    /// ```
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        check_timeout(timeout)?;
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    /// The timeout set by [`set_read_timeout`](BlockingReader::set_read_timeout).
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Get a reference to the wrapped reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the wrapped reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Unwrap the asynchronous reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin> Read for BlockingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let inner = &mut self.inner;
+        block_on_io(self.timeout, |cx| Pin::new(&mut *inner).poll_read(cx, buf))
+    }
+}
+
+/// Adapts an [`AsyncWrite`] into a blocking [`Write`] by blocking on every write.
+#[derive(Debug)]
+pub struct BlockingWriter<W> {
+    inner: W,
+    timeout: Option<Duration>,
+}
+
+impl<W> BlockingWriter<W> {
+    /// Wrap an asynchronous writer.
+    pub fn new(inner: W) -> Self {
+        Self { inner, timeout: None }
+    }
+
+    /// Set how long each write, flush or close may block before failing with
+    /// [`io::ErrorKind::TimedOut`], like
+    /// [`TcpStream::set_write_timeout`](std::net::TcpStream::set_write_timeout). `None` means
+    /// they block for as long as it takes.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] if `timeout` is zero.
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        check_timeout(timeout)?;
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    /// The timeout set by [`set_write_timeout`](BlockingWriter::set_write_timeout).
+    pub fn write_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Get a reference to the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the wrapped writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Unwrap the asynchronous writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: AsyncWrite + Unpin> BlockingWriter<W> {
+    /// Flush and close the wrapped writer.
+    pub fn close(&mut self) -> io::Result<()> {
+        let inner = &mut self.inner;
+        block_on_io(self.timeout, |cx| Pin::new(&mut *inner).poll_close(cx))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> Write for BlockingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let inner = &mut self.inner;
+        block_on_io(self.timeout, |cx| Pin::new(&mut *inner).poll_write(cx, buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let inner = &mut self.inner;
+        block_on_io(self.timeout, |cx| Pin::new(&mut *inner).poll_flush(cx))
+    }
+}
+
+/// Copy the entire contents of an asynchronous reader into a blocking writer, blocking the current
+/// thread until the reader reaches EOF. The writer is flushed afterwards.
+///
+/// Returns the number of bytes copied.
+///
+/// # Example
+///
+/// ```
+/// let mut body = &b"downloaded bytes"[..];
+/// let mut file = Vec::new();
+///
+/// let copied = pollster::io::copy_blocking(&mut body, &mut file).unwrap();
+/// assert_eq!(copied, 16);
+/// assert_eq!(file, b"downloaded bytes");
+/// ```
+pub fn copy_blocking<R, W>(reader: &mut R, writer: &mut W) -> io::Result<u64>
+where
+    R: AsyncRead + Unpin + ?Sized,
+    W: Write + ?Sized,
+{
+    let copied = io::copy(&mut BlockingReader::new(reader), writer)?;
+    writer.flush()?;
+    Ok(copied)
+}
+
+/// Copy the entire contents of a blocking reader into an asynchronous writer, blocking the current
+/// thread until the reader reaches EOF. The writer is flushed afterwards.
+///
+/// Returns the number of bytes copied.
+pub fn copy_blocking_to_async<R, W>(reader: &mut R, writer: &mut W) -> io::Result<u64>
+where
+    R: Read + ?Sized,
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    let mut writer = BlockingWriter::new(writer);
+    let copied = io::copy(reader, &mut writer)?;
+    writer.flush()?;
+    Ok(copied)
+}
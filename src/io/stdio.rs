@@ -0,0 +1,226 @@
+use futures_io::{AsyncRead, AsyncWrite};
+use std::{
+    io::{self, Read, Write},
+    mem,
+    pin::Pin,
+    sync::{Condvar, Mutex, OnceLock},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+struct Helper<S> {
+    state: Mutex<S>,
+    cond: Condvar,
+}
+
+impl<S: Send + 'static> Helper<S> {
+    fn spawn(name: &str, state: S, run: fn(&'static Self)) -> &'static Self {
+        let helper: &'static Self = Box::leak(Box::new(Self {
+            state: Mutex::new(state),
+            cond: Condvar::new(),
+        }));
+        thread::Builder::new()
+            .name(name.into())
+            .spawn(move || run(helper))
+            .expect("failed to spawn stdio thread");
+        helper
+    }
+}
+
+fn register(wakers: &mut Vec<Waker>, waker: &Waker) {
+    if !wakers.iter().any(|w| w.will_wake(waker)) {
+        wakers.push(waker.clone());
+    }
+}
+
+struct StdinState {
+    buffer: Vec<u8>,
+    pos: usize,
+    // Set by readers when the buffer runs dry; the helper thread only reads stdin when asked to.
+    wanted: bool,
+    eof: bool,
+    error: Option<io::Error>,
+    wakers: Vec<Waker>,
+}
+
+/// Get an asynchronous handle to the process's standard input.
+///
+/// Reads are performed by a dedicated helper thread, started on first use. The thread only reads
+/// from stdin while a [`Stdin`] handle is waiting for data.
+pub fn stdin() -> Stdin {
+    static HELPER: OnceLock<&'static Helper<StdinState>> = OnceLock::new();
+
+    let helper = HELPER.get_or_init(|| {
+        let state = StdinState {
+            buffer: Vec::new(),
+            pos: 0,
+            wanted: false,
+            eof: false,
+            error: None,
+            wakers: Vec::new(),
+        };
+        Helper::spawn("pollster-stdin", state, read_stdin)
+    });
+    Stdin { helper }
+}
+
+fn read_stdin(helper: &'static Helper<StdinState>) {
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        let mut state = helper.state.lock().unwrap();
+        while !state.wanted {
+            state = helper.cond.wait(state).unwrap();
+        }
+        drop(state);
+
+        let result = io::stdin().lock().read(&mut chunk);
+
+        let mut state = helper.state.lock().unwrap();
+        state.wanted = false;
+        match result {
+            Ok(0) => state.eof = true,
+            Ok(n) => {
+                state.buffer.clear();
+                state.buffer.extend_from_slice(&chunk[..n]);
+                state.pos = 0;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => state.wanted = true,
+            Err(e) => state.error = Some(e),
+        }
+        state.wakers.drain(..).for_each(Waker::wake);
+    }
+}
+
+/// An [`AsyncRead`] handle to standard input, returned by [`stdin`].
+#[derive(Clone)]
+pub struct Stdin {
+    helper: &'static Helper<StdinState>,
+}
+
+impl AsyncRead for Stdin {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let mut state = self.helper.state.lock().unwrap();
+        let available = &state.buffer[state.pos..];
+        if !available.is_empty() {
+            let n = available.len().min(buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            state.pos += n;
+            Poll::Ready(Ok(n))
+        } else if let Some(e) = state.error.take() {
+            Poll::Ready(Err(e))
+        } else if mem::take(&mut state.eof) || buf.is_empty() {
+            Poll::Ready(Ok(0))
+        } else {
+            if !state.wanted {
+                state.wanted = true;
+                self.helper.cond.notify_one();
+            }
+            register(&mut state.wakers, cx.waker());
+            Poll::Pending
+        }
+    }
+}
+
+struct StdoutState {
+    buffer: Vec<u8>,
+    // Whether the helper thread is currently writing a batch taken from `buffer`.
+    busy: bool,
+    error: Option<io::Error>,
+    wakers: Vec<Waker>,
+}
+
+/// Get an asynchronous handle to the process's standard output.
+///
+/// Writes are buffered and handed to a dedicated helper thread, started on first use. Flush the
+/// handle before exiting to make sure everything written has reached stdout.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Write;
+/// use pollster::io::BlockingWriter;
+///
+/// let mut out = BlockingWriter::new(pollster::io::stdout());
+/// writeln!(out, "hello from a helper thread").unwrap();
+/// out.flush().unwrap();
+/// ```
+pub fn stdout() -> Stdout {
+    static HELPER: OnceLock<&'static Helper<StdoutState>> = OnceLock::new();
+
+    let helper = HELPER.get_or_init(|| {
+        let state = StdoutState {
+            buffer: Vec::new(),
+            busy: false,
+            error: None,
+            wakers: Vec::new(),
+        };
+        Helper::spawn("pollster-stdout", state, write_stdout)
+    });
+    Stdout { helper }
+}
+
+fn write_stdout(helper: &'static Helper<StdoutState>) {
+    loop {
+        let mut state = helper.state.lock().unwrap();
+        while state.buffer.is_empty() {
+            state = helper.cond.wait(state).unwrap();
+        }
+        let batch = mem::take(&mut state.buffer);
+        state.busy = true;
+        // Writers blocked on a full buffer can continue now.
+        state.wakers.drain(..).for_each(Waker::wake);
+        drop(state);
+
+        let mut out = io::stdout().lock();
+        let result = out.write_all(&batch).and_then(|()| out.flush());
+        drop(out);
+
+        let mut state = helper.state.lock().unwrap();
+        state.busy = false;
+        if let Err(e) = result {
+            state.error = Some(e);
+        }
+        state.wakers.drain(..).for_each(Waker::wake);
+    }
+}
+
+/// An [`AsyncWrite`] handle to standard output, returned by [`stdout`].
+#[derive(Clone)]
+pub struct Stdout {
+    helper: &'static Helper<StdoutState>,
+}
+
+impl AsyncWrite for Stdout {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let mut state = self.helper.state.lock().unwrap();
+        if let Some(e) = state.error.take() {
+            Poll::Ready(Err(e))
+        } else if state.buffer.len() >= CHUNK_SIZE {
+            register(&mut state.wakers, cx.waker());
+            Poll::Pending
+        } else {
+            let n = buf.len().min(CHUNK_SIZE - state.buffer.len());
+            state.buffer.extend_from_slice(&buf[..n]);
+            self.helper.cond.notify_one();
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut state = self.helper.state.lock().unwrap();
+        if let Some(e) = state.error.take() {
+            Poll::Ready(Err(e))
+        } else if state.buffer.is_empty() && !state.busy {
+            Poll::Ready(Ok(()))
+        } else {
+            register(&mut state.wakers, cx.waker());
+            Poll::Pending
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
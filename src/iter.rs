@@ -0,0 +1,153 @@
+//! Bridges from blocking iterators to asynchronous streams.
+
+use futures_core::Stream;
+use std::{
+    any::Any,
+    collections::VecDeque,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+const DEFAULT_CAPACITY: usize = 32;
+
+struct State<T> {
+    buffer: VecDeque<T>,
+    capacity: usize,
+    // Set by the helper thread once the iterator is exhausted (or has panicked).
+    done: bool,
+    panic: Option<Box<dyn Any + Send>>,
+    // Set when the stream is dropped, telling the helper thread to stop.
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    space: Condvar,
+}
+
+/// Move a blocking iterator onto a helper thread and expose its items as a [`Stream`].
+///
+/// Up to 32 items are buffered ahead of the consumer; use [`into_stream_with_capacity`] to pick a
+/// different bound. The helper thread stops once the stream is dropped and the iterator yields its
+/// next item. If the iterator panics, the panic is resumed on the thread polling the stream.
+///
+/// # Example
+///
+/// ```
+/// use futures_core::Stream;
+/// use std::{future::poll_fn, pin::Pin};
+///
+/// let mut stream = pollster::iter::into_stream(vec![1, 2, 3]);
+/// let mut next = || pollster::block_on(poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)));
+///
+/// assert_eq!(next(), Some(1));
+/// assert_eq!(next(), Some(2));
+/// assert_eq!(next(), Some(3));
+/// assert_eq!(next(), None);
+/// ```
+pub fn into_stream<I>(iter: I) -> IntoStream<I::Item>
+where
+    I: IntoIterator,
+    I::IntoIter: Send + 'static,
+    I::Item: Send + 'static,
+{
+    into_stream_with_capacity(iter, DEFAULT_CAPACITY)
+}
+
+/// Like [`into_stream`], but buffering up to `capacity` items (at least one).
+pub fn into_stream_with_capacity<I>(iter: I, capacity: usize) -> IntoStream<I::Item>
+where
+    I: IntoIterator,
+    I::IntoIter: Send + 'static,
+    I::Item: Send + 'static,
+{
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            buffer: VecDeque::new(),
+            capacity: capacity.max(1),
+            done: false,
+            panic: None,
+            closed: false,
+            waker: None,
+        }),
+        space: Condvar::new(),
+    });
+
+    let iter = iter.into_iter();
+    let producer = Arc::clone(&shared);
+    thread::Builder::new()
+        .name("pollster-iter".into())
+        .spawn(move || produce(iter, &producer))
+        .expect("failed to spawn iterator thread");
+
+    IntoStream { shared }
+}
+
+fn produce<I: Iterator>(iter: I, shared: &Shared<I::Item>) {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        for item in iter {
+            let mut state = shared.state.lock().unwrap();
+            while state.buffer.len() >= state.capacity && !state.closed {
+                state = shared.space.wait(state).unwrap();
+            }
+            if state.closed {
+                return;
+            }
+            state.buffer.push_back(item);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }));
+
+    let mut state = shared.state.lock().unwrap();
+    state.done = true;
+    state.panic = result.err();
+    if let Some(waker) = state.waker.take() {
+        waker.wake();
+    }
+}
+
+/// Stream returned by [`into_stream`].
+pub struct IntoStream<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Stream for IntoStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(item) = state.buffer.pop_front() {
+            self.shared.space.notify_one();
+            Poll::Ready(Some(item))
+        } else if let Some(payload) = state.panic.take() {
+            drop(state);
+            panic::resume_unwind(payload)
+        } else if state.done {
+            Poll::Ready(None)
+        } else {
+            match &state.waker {
+                Some(waker) if waker.will_wake(cx.waker()) => {}
+                _ => state.waker = Some(cx.waker().clone()),
+            }
+            Poll::Pending
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let state = self.shared.state.lock().unwrap();
+        (state.buffer.len(), if state.done { Some(state.buffer.len()) } else { None })
+    }
+}
+
+impl<T> Drop for IntoStream<T> {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().closed = true;
+        self.shared.space.notify_one();
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{
+    future::{Either, Remaining},
+    ready::ReadyQueue,
+};
+use std::{
+    future::{poll_fn, Future},
+    pin::{pin, Pin},
+    task::{Context, Poll},
+};
+
+// Poll the future in `slot`, dropping it in place and returning its output once it completes.
+fn poll_in_place<F: Future>(mut slot: Pin<&mut Option<F>>, cx: &mut Context<'_>) -> Option<F::Output> {
+    let output = match slot.as_mut().as_pin_mut()?.poll(cx) {
+        Poll::Ready(output) => output,
+        Poll::Pending => return None,
+    };
+    slot.set(None);
+    Some(output)
+}
+
+/// Block the thread until both fallible futures succeed, or until either of them fails.
+///
+/// The futures are driven concurrently. As soon as one of them returns an error, the other is
+/// dropped and the error is returned.
+///
+/// # Example
+///
+/// ```
+/// let ok = pollster::try_join(async { Ok::<_, ()>(1) }, async { Ok(2) });
+/// assert_eq!(ok, Ok((1, 2)));
+///
+/// let err = pollster::try_join(std::future::pending::<Result<(), _>>(), async { Err::<(), _>("fatal") });
+/// assert_eq!(err, Err("fatal"));
+/// ```
+#[track_caller]
+pub fn try_join<A, B, T, U, E>(a: A, b: B) -> Result<(T, U), E>
+where
+    A: Future<Output = Result<T, E>>,
+    B: Future<Output = Result<U, E>>,
+{
+    let (mut a, mut b) = (pin!(Some(a)), pin!(Some(b)));
+    let (mut a_out, mut b_out) = (None, None);
+    let ready = ReadyQueue::with_len(2);
+    crate::block_on(poll_fn(|cx| {
+        for index in ready.take(cx.waker()) {
+            let cx = &mut ready.context(index);
+            if index == 0 {
+                if let Some(result) = poll_in_place(a.as_mut(), cx) {
+                    a_out = Some(result?);
+                }
+            } else if let Some(result) = poll_in_place(b.as_mut(), cx) {
+                b_out = Some(result?);
+            }
+        }
+        if a_out.is_some() && b_out.is_some() {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }))?;
+    Ok((a_out.unwrap(), b_out.unwrap()))
+}
+
+/// Block the thread until every fallible future succeeds, or until any of them fails.
+///
+/// The futures are driven concurrently and their outputs are returned in input order. As soon as
+/// one of them returns an error, the rest are dropped and the error is returned. Each future is
+/// only polled again once it has been woken, so joining many futures stays cheap.
+///
+/// # Example
+///
+/// ```
+/// let all = pollster::try_join_all((1..=3).map(|i| async move { Ok::<_, ()>(i * 10) }));
+/// assert_eq!(all, Ok(vec![10, 20, 30]));
+/// ```
+#[track_caller]
+pub fn try_join_all<I, F, T, E>(futures: I) -> Result<Vec<T>, E>
+where
+    I: IntoIterator<Item = F>,
+    F: Future<Output = Result<T, E>>,
+{
+    let mut futures: Box<[Option<F>]> = futures.into_iter().map(Some).collect();
+    let mut outputs: Vec<Option<T>> = futures.iter().map(|_| None).collect();
+    let mut remaining = futures.len();
+    let ready = ReadyQueue::with_len(futures.len());
+    crate::block_on(poll_fn(|cx| {
+        for index in ready.take(cx.waker()) {
+            // SAFETY: the boxed slice is never reallocated, so the futures never move.
+            let fut = unsafe { Pin::new_unchecked(&mut futures[index]) };
+            if let Some(result) = poll_in_place(fut, &mut ready.context(index)) {
+                outputs[index] = Some(result?);
+                remaining -= 1;
+            }
+        }
+        if remaining == 0 {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }))?;
+    Ok(outputs.into_iter().map(Option::unwrap).collect())
+}
+
+/// Block the thread until every future completes, running at most `limit` of them at once, and
+/// return their outputs in input order.
+///
+/// Futures are taken from the iterator as earlier ones complete, so a large batch never has more
+/// than `limit` futures, or whatever resources they hold, alive at a time.
+///
+/// # Panics
+///
+/// Panics if `limit` is 0.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::time::Duration;
+///
+/// let running = AtomicUsize::new(0);
+/// let outputs = pollster::join_all_limited(
+///     (0..10).map(|i| {
+///         let running = &running;
+///         async move {
+///             assert!(running.fetch_add(1, Ordering::SeqCst) < 3);
+///             pollster::time::sleep(Duration::from_millis(1)).await;
+///             running.fetch_sub(1, Ordering::SeqCst);
+///             i * 2
+///         }
+///     }),
+///     3,
+/// );
+/// assert_eq!(outputs, (0..10).map(|i| i * 2).collect::<Vec<_>>());
+/// ```
+#[track_caller]
+pub fn join_all_limited<I, F>(futures: I, limit: usize) -> Vec<F::Output>
+where
+    I: IntoIterator<Item = F>,
+    F: Future,
+{
+    assert!(limit > 0, "concurrency limit must be non-zero");
+    let mut futures = futures.into_iter().enumerate().fuse();
+    // Each running future with the index of its output.
+    let mut slots: Box<[(usize, Option<F>)]> = (0..limit).map(|_| (0, None)).collect();
+    let mut outputs: Vec<Option<F::Output>> = Vec::new();
+    let ready = ReadyQueue::with_len(limit);
+    crate::block_on(poll_fn(|cx| loop {
+        for (slot, (index, fut)) in slots.iter_mut().enumerate() {
+            if fut.is_none() {
+                if let Some((next, new)) = futures.next() {
+                    outputs.push(None);
+                    *index = next;
+                    *fut = Some(new);
+                    ready.schedule(slot);
+                }
+            }
+        }
+        if slots.iter().all(|(_, fut)| fut.is_none()) {
+            return Poll::Ready(());
+        }
+
+        let mut progressed = false;
+        for slot in ready.take(cx.waker()) {
+            let (index, fut) = &mut slots[slot];
+            // SAFETY: the boxed slice is never reallocated, and a slot is only refilled once its
+            // future has been dropped in place.
+            let fut = unsafe { Pin::new_unchecked(fut) };
+            if let Some(output) = poll_in_place(fut, &mut ready.context(slot)) {
+                outputs[*index] = Some(output);
+                progressed = true;
+            }
+        }
+
+        if !progressed {
+            return Poll::Pending;
+        }
+    }));
+    outputs.into_iter().map(Option::unwrap).collect()
+}
+
+/// Block the thread until the first of several fallible futures succeeds, or until all of them
+/// have failed.
+///
+/// The futures are driven concurrently. The first success is returned and the remaining futures
+/// are dropped. If every future fails, all of the errors are returned in input order.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// let mirror = |delay, ok: bool| async move {
+///     pollster::time::sleep(Duration::from_millis(delay)).await;
+///     if ok { Ok(delay) } else { Err(delay) }
+/// };
+/// assert_eq!(pollster::race_ok(vec![mirror(30, true), mirror(1, false), mirror(10, true)]), Ok(10));
+/// assert_eq!(pollster::race_ok(vec![mirror(5, false), mirror(1, false)]), Err::<u64, _>(vec![5, 1]));
+/// ```
+#[track_caller]
+pub fn race_ok<I, F, T, E>(futures: I) -> Result<T, Vec<E>>
+where
+    I: IntoIterator<Item = F>,
+    F: Future<Output = Result<T, E>>,
+{
+    let mut futures: Box<[Option<F>]> = futures.into_iter().map(Some).collect();
+    let mut errors: Vec<Option<E>> = futures.iter().map(|_| None).collect();
+    let mut remaining = futures.len();
+    let ready = ReadyQueue::with_len(futures.len());
+    let success = crate::block_on(poll_fn(|cx| {
+        for index in ready.take(cx.waker()) {
+            // SAFETY: the boxed slice is never reallocated, so the futures never move.
+            let fut = unsafe { Pin::new_unchecked(&mut futures[index]) };
+            match poll_in_place(fut, &mut ready.context(index)) {
+                Some(Ok(value)) => return Poll::Ready(Some(value)),
+                Some(Err(err)) => {
+                    errors[index] = Some(err);
+                    remaining -= 1;
+                }
+                None => {}
+            }
+        }
+        if remaining == 0 {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }));
+    success.ok_or_else(|| errors.into_iter().map(Option::unwrap).collect())
+}
+
+/// Block the thread until the first of two futures completes, returning its output along with the
+/// other future.
+///
+/// The futures are driven concurrently, `a` first. Both are pinned on the heap, so the one that
+/// is still pending comes back as a [`Remaining`] that can be driven further or dropped.
+///
+/// # Example
+///
+/// ```
+/// use pollster::future::Either;
+/// use std::time::Duration;
+///
+/// let slow = pollster::time::sleep(Duration::from_millis(10));
+/// match pollster::block_on_race(async { "fast" }, slow) {
+///     Either::Left((out, slow)) => {
+///         assert_eq!(out, "fast");
+///         pollster::block_on(slow);
+///     }
+///     Either::Right(_) => unreachable!(),
+/// }
+///
+/// // With matching outputs, `factor_first` splits off the winner's value.
+/// let (value, _rest) = pollster::block_on_race(async { 1 }, std::future::pending()).factor_first();
+/// assert_eq!(value, 1);
+/// ```
+#[track_caller]
+#[allow(clippy::type_complexity)]
+pub fn block_on_race<A, B>(a: A, b: B) -> Either<(A::Output, Remaining<B>), (B::Output, Remaining<A>)>
+where
+    A: Future,
+    B: Future,
+{
+    let (mut a, mut b) = (Box::pin(a), Box::pin(b));
+    let ready = ReadyQueue::with_len(2);
+    let winner = crate::block_on(poll_fn(|cx| {
+        for index in ready.take(cx.waker()) {
+            let cx = &mut ready.context(index);
+            if index == 0 {
+                if let Poll::Ready(out) = a.as_mut().poll(cx) {
+                    return Poll::Ready(Either::Left(out));
+                }
+            } else if let Poll::Ready(out) = b.as_mut().poll(cx) {
+                return Poll::Ready(Either::Right(out));
+            }
+        }
+        Poll::Pending
+    }));
+    match winner {
+        Either::Left(out) => Either::Left((out, Remaining::new(b))),
+        Either::Right(out) => Either::Right((out, Remaining::new(a))),
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A set of up to `N` futures of one type, stored inline, that yields their outputs as they
+/// complete.
+///
+/// Nothing is boxed, so the set suits code that avoids allocating. The futures are polled where
+/// they sit, which means the set has to be pinned before futures can be added, with
+/// [`pin!`](std::pin::pin) or [`Box::pin`]. Every poll of the set polls each of its pending
+/// futures, so it is meant for small `N`.
+///
+/// The futures only run while the set is polled, so nothing keeps running in the background:
+/// dropping the set, say when a timeout around the code owning it fires, drops every future in
+/// it on the spot.
+///
+/// # Example
+///
+/// ```
+/// use std::{pin::pin, time::Duration};
+/// use pollster::ArrayJoinSet;
+///
+/// let request = |ms| async move {
+///     pollster::time::sleep(Duration::from_millis(ms)).await;
+///     ms
+/// };
+/// let mut set = pin!(ArrayJoinSet::<_, 3>::new());
+/// for ms in [30, 10, 20] {
+///     assert!(set.as_mut().try_push(request(ms)).is_ok());
+/// }
+/// assert!(set.as_mut().try_push(request(0)).is_err());
+///
+/// let mut done = Vec::new();
+/// while let Some(ms) = set.as_mut().join_next_blocking() {
+///     done.push(ms);
+/// }
+/// assert_eq!(done, [10, 20, 30]);
+/// ```
+pub struct ArrayJoinSet<F, const N: usize> {
+    slots: [Option<F>; N],
+    len: usize,
+    // Where the next poll starts, so an early slot that is always ready can't starve the rest.
+    next: usize,
+}
+
+impl<F, const N: usize> ArrayJoinSet<F, N> {
+    const EMPTY: Option<F> = None;
+
+    /// Create an empty set.
+    pub const fn new() -> Self {
+        Self {
+            slots: [Self::EMPTY; N],
+            len: 0,
+            next: 0,
+        }
+    }
+
+    /// How many futures the set can hold.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// How many futures are still running.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no futures are running.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Add a future to the set, or hand it back if the set is full.
+    pub fn try_push(self: Pin<&mut Self>, fut: F) -> Result<(), F> {
+        // SAFETY: the future goes into an empty slot; nothing already pinned is moved.
+        let this = unsafe { self.get_unchecked_mut() };
+        match this.slots.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some(fut);
+                this.len += 1;
+                Ok(())
+            }
+            None => Err(fut),
+        }
+    }
+
+    /// Drop every future in the set.
+    pub fn clear(self: Pin<&mut Self>) {
+        // SAFETY: the futures are dropped in place.
+        let this = unsafe { self.get_unchecked_mut() };
+        this.slots.iter_mut().for_each(|slot| *slot = None);
+        this.len = 0;
+    }
+}
+
+impl<F: Future, const N: usize> ArrayJoinSet<F, N> {
+    /// Wait for the next future to complete, resolving to its output, or to `None` straight away
+    /// if the set is empty.
+    pub fn join_next(self: Pin<&mut Self>) -> JoinNext<'_, F, N> {
+        JoinNext { set: self }
+    }
+
+    /// Block the current thread until the next future completes, returning its output, or `None`
+    /// straight away if the set is empty.
+    #[track_caller]
+    pub fn join_next_blocking(self: Pin<&mut Self>) -> Option<F::Output> {
+        crate::block_on(self.join_next())
+    }
+
+    /// Poll the futures in the set, returning the output of the first one found complete.
+    /// Returns `Poll::Ready(None)` if the set is empty.
+    pub fn poll_join_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<F::Output>> {
+        // SAFETY: the futures are never moved, and completed ones are dropped in place.
+        let this = unsafe { self.get_unchecked_mut() };
+        if this.len == 0 {
+            return Poll::Ready(None);
+        }
+        for offset in 0..N {
+            let index = (this.next + offset) % N;
+            let slot = &mut this.slots[index];
+            if let Some(fut) = slot {
+                if let Poll::Ready(output) = unsafe { Pin::new_unchecked(fut) }.poll(cx) {
+                    *slot = None;
+                    this.len -= 1;
+                    this.next = (index + 1) % N;
+                    return Poll::Ready(Some(output));
+                }
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl<F, const N: usize> Default for ArrayJoinSet<F, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F, const N: usize> fmt::Debug for ArrayJoinSet<F, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArrayJoinSet")
+            .field("len", &self.len)
+            .field("capacity", &N)
+            .finish()
+    }
+}
+
+/// Future returned by [`ArrayJoinSet::join_next`].
+#[derive(Debug)]
+pub struct JoinNext<'a, F, const N: usize> {
+    set: Pin<&'a mut ArrayJoinSet<F, N>>,
+}
+
+impl<F: Future, const N: usize> Future for JoinNext<'_, F, N> {
+    type Output = Option<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.set.as_mut().poll_join_next(cx)
+    }
+}
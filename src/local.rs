@@ -0,0 +1,745 @@
+//! A pool of futures that don't need to be [`Send`], all run on the thread that drives the pool.
+
+use crate::{
+    coop, enter,
+    executor::{self, Executor, Handle, PanicPolicy, SendFuture, Spawn, TaskInfo, TaskState},
+    pool::JoinHandle,
+};
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    future::{poll_fn, Future},
+    mem,
+    panic::{self, Location},
+    pin::Pin,
+    rc::{Rc, Weak},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Wake, Waker},
+};
+
+type LocalFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+// A spawned future that the pool hasn't adopted yet.
+struct Incoming {
+    fut: LocalFuture,
+    name: Option<&'static str>,
+    location: &'static Location<'static>,
+}
+
+// A task's id with the generation of the task currently holding it.
+type TaskId = (usize, u32);
+
+struct ReadyState {
+    ids: VecDeque<TaskId>,
+    waker: Option<Waker>,
+    // Futures spawned through a `Handle`, possibly from other threads.
+    spawned: Vec<(SendFuture, &'static Location<'static>)>,
+    // Set once the pool is dropped, so handles stop accepting futures.
+    closed: bool,
+}
+
+// Ids of the tasks that have been woken, shared with their wakers, which may be sent to other
+// threads.
+struct ReadyQueue {
+    state: Mutex<ReadyState>,
+}
+
+impl ReadyQueue {
+    fn push(&self, id: TaskId) {
+        let mut state = self.state.lock().unwrap();
+        state.ids.push_back(id);
+        let waker = state.waker.take();
+        drop(state);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    // Take every woken id, registering `waker` to hear about the next ones.
+    fn take(&self, waker: &Waker) -> VecDeque<TaskId> {
+        let mut state = self.state.lock().unwrap();
+        match &state.waker {
+            Some(current) if current.will_wake(waker) => {}
+            _ => state.waker = Some(waker.clone()),
+        }
+        mem::take(&mut state.ids)
+    }
+
+    fn restore(&self, ids: VecDeque<TaskId>) {
+        let mut state = self.state.lock().unwrap();
+        let newer = mem::replace(&mut state.ids, ids);
+        state.ids.extend(newer);
+    }
+}
+
+// Spawns for a `Handle`, waking the pool so that it adopts the future.
+struct Remote(std::sync::Weak<ReadyQueue>);
+
+impl Spawn for Remote {
+    fn spawn(&self, fut: SendFuture, location: &'static Location<'static>) -> Result<(), SpawnError> {
+        let ready = self.0.upgrade().ok_or_else(SpawnError::new)?;
+        let mut state = ready.state.lock().unwrap();
+        if state.closed {
+            return Err(SpawnError::new());
+        }
+        state.spawned.push((fut, location));
+        let waker = state.waker.take();
+        drop(state);
+        #[cfg(feature = "stats")]
+        crate::stats::record_spawn();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        Ok(())
+    }
+}
+
+// Kept in a slab by task id and reused by later tasks with the same id, bumping the generation so
+// that wakes meant for an earlier task are ignored.
+struct TaskWaker {
+    id: usize,
+    generation: AtomicU32,
+    queued: AtomicBool,
+    ready: Arc<ReadyQueue>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        if !self.queued.swap(true, Ordering::AcqRel) {
+            self.ready.push((self.id, self.generation.load(Ordering::Acquire)));
+        }
+    }
+}
+
+// Operations a task may perform per poll before it is made to yield, as in tokio.
+const DEFAULT_BUDGET: u32 = 128;
+
+struct Entry {
+    fut: LocalFuture,
+    name: Option<&'static str>,
+    location: &'static Location<'static>,
+    polls: u64,
+    generation: u32,
+}
+
+/// A pool of futures run on the thread that calls one of its `run` methods.
+///
+/// Futures are added through a [`LocalSpawner`], including from inside other futures of the pool,
+/// and only make progress while the pool is being run.
+///
+/// A task's waker is kept when the task completes and handed to the next task that takes its
+/// place, so spawning many short tasks doesn't allocate a waker for each.
+///
+/// # Example
+///
+/// ```
+/// use std::{cell::Cell, rc::Rc};
+/// use pollster::local::LocalPool;
+///
+/// let mut pool = LocalPool::new();
+/// let spawner = pool.spawner();
+/// let total = Rc::new(Cell::new(0));
+/// for i in 1..=3 {
+///     let total = Rc::clone(&total);
+///     spawner.spawn_local(async move { total.set(total.get() + i) }).unwrap();
+/// }
+/// pool.run();
+/// assert_eq!(total.get(), 6);
+/// ```
+pub struct LocalPool {
+    // Borrowed mutably while the pool is running.
+    tasks: RefCell<Tasks>,
+    incoming: Rc<RefCell<Vec<Incoming>>>,
+    ready: Arc<ReadyQueue>,
+    budget: Option<u32>,
+    on_task_panic: PanicPolicy,
+}
+
+struct Tasks {
+    entries: Vec<Option<Entry>>,
+    // One per slot of `entries`, outliving the tasks that use it.
+    wakers: Vec<(Arc<TaskWaker>, Waker)>,
+    free: Vec<usize>,
+    #[cfg(feature = "test-util")]
+    shuffle: Option<Shuffle>,
+}
+
+impl LocalPool {
+    /// Create an empty pool with the default cooperative budget of 128 operations per poll.
+    pub fn new() -> Self {
+        Self {
+            tasks: RefCell::new(Tasks {
+                entries: Vec::new(),
+                wakers: Vec::new(),
+                free: Vec::new(),
+                #[cfg(feature = "test-util")]
+                shuffle: None,
+            }),
+            incoming: Rc::new(RefCell::new(Vec::new())),
+            ready: Arc::new(ReadyQueue {
+                state: Mutex::new(ReadyState {
+                    ids: VecDeque::new(),
+                    waker: None,
+                    spawned: Vec::new(),
+                    closed: false,
+                }),
+            }),
+            budget: Some(DEFAULT_BUDGET),
+            on_task_panic: PanicPolicy::Propagate,
+        }
+    }
+
+    /// Create a pool that polls woken tasks in a pseudo-random order, to shake out bugs that
+    /// depend on how tasks interleave. Requires the `test-util` feature.
+    ///
+    /// The order is determined by a seed, taken from the `POLLSTER_SEED` environment variable if
+    /// it is set and chosen at random otherwise. If the thread panics while the pool is alive,
+    /// such as on a failed assertion, the seed is printed to stderr so the failing order can be
+    /// replayed by setting `POLLSTER_SEED`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{cell::RefCell, rc::Rc};
+    /// use pollster::local::LocalPool;
+    ///
+    /// let order = |seed| {
+    ///     let mut pool = LocalPool::with_seed(seed);
+    ///     let order = Rc::new(RefCell::new(Vec::new()));
+    ///     for i in 0..8 {
+    ///         let order = Rc::clone(&order);
+    ///         pool.spawner().spawn_local(async move { order.borrow_mut().push(i) }).unwrap();
+    ///     }
+    ///     pool.run();
+    ///     Rc::try_unwrap(order).unwrap().into_inner()
+    /// };
+    /// // The same seed always gives the same order.
+    /// assert_eq!(order(7), order(7));
+    /// ```
+    #[cfg(feature = "test-util")]
+    pub fn shuffled() -> Self {
+        let seed = std::env::var("POLLSTER_SEED")
+            .ok()
+            .and_then(|seed| seed.trim().parse().ok())
+            .unwrap_or_else(Shuffle::random_seed);
+        Self::with_seed(seed)
+    }
+
+    /// Create a pool that polls woken tasks in the pseudo-random order given by `seed`, like
+    /// [`shuffled`](LocalPool::shuffled). Requires the `test-util` feature.
+    #[cfg(feature = "test-util")]
+    pub fn with_seed(seed: u64) -> Self {
+        let mut pool = Self::new();
+        pool.tasks.get_mut().shuffle = Some(Shuffle::new(seed));
+        pool
+    }
+
+    /// The seed of a pool created by [`shuffled`](LocalPool::shuffled) or
+    /// [`with_seed`](LocalPool::with_seed). Requires the `test-util` feature.
+    #[cfg(feature = "test-util")]
+    pub fn seed(&self) -> Option<u64> {
+        self.tasks.borrow().shuffle.as_ref().map(|shuffle| shuffle.seed)
+    }
+
+    /// Set how many operations each poll of a task, or of the future passed to
+    /// [`run_until`](LocalPool::run_until), may perform before it is made to yield. `None` means no
+    /// limit.
+    ///
+    /// Operations are counted by the crate's channels and by
+    /// [`task::consume_budget`](crate::task::consume_budget), so a task that keeps finding work
+    /// ready can't starve the other tasks on the thread.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{cell::Cell, rc::Rc};
+    /// use pollster::local::LocalPool;
+    ///
+    /// let mut pool = LocalPool::new();
+    /// pool.set_budget(Some(10));
+    /// let spawner = pool.spawner();
+    /// let ticks = Rc::new(Cell::new(0));
+    /// let busy_ticks = Rc::clone(&ticks);
+    /// spawner.spawn_local(async move {
+    ///     loop {
+    ///         pollster::task::consume_budget().await;
+    ///         busy_ticks.set(busy_ticks.get() + 1);
+    ///     }
+    /// }).unwrap();
+    /// // The busy task yields every 10 operations, so the main future still gets to finish.
+    /// pool.run_until(async { while ticks.get() < 100 { pollster::task::consume_budget().await } });
+    /// ```
+    pub fn set_budget(&mut self, budget: Option<u32>) {
+        self.budget = budget;
+    }
+
+    /// Set what happens when a task panics. The default is [`PanicPolicy::Propagate`].
+    ///
+    /// A propagated panic unwinds out of the `run` call once the task that panicked has been
+    /// dropped, and the pool can be run again to finish the other tasks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{cell::Cell, panic::{self, AssertUnwindSafe}, rc::Rc};
+    /// use pollster::local::LocalPool;
+    ///
+    /// let mut pool = LocalPool::new();
+    /// let spawner = pool.spawner();
+    /// let done = Rc::new(Cell::new(false));
+    /// spawner.spawn_local(async { panic!("oops") }).unwrap();
+    /// spawner.spawn_local({
+    ///     let done = Rc::clone(&done);
+    ///     async move { done.set(true) }
+    /// }).unwrap();
+    ///
+    /// assert!(panic::catch_unwind(AssertUnwindSafe(|| pool.run())).is_err());
+    /// pool.run();
+    /// assert!(done.get());
+    /// ```
+    pub fn set_on_task_panic(&mut self, policy: PanicPolicy) {
+        self.on_task_panic = policy;
+    }
+
+    /// Get a handle for spawning futures onto the pool.
+    pub fn spawner(&self) -> LocalSpawner {
+        LocalSpawner {
+            incoming: Rc::downgrade(&self.incoming),
+        }
+    }
+
+    /// Get a thread-safe [`Handle`] for spawning onto the pool from other threads, which doesn't
+    /// keep the pool alive.
+    ///
+    /// Futures spawned through the handle must be [`Send`], and run on the pool's thread the next
+    /// time it is run.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use pollster::local::LocalPool;
+    ///
+    /// let mut pool = LocalPool::new();
+    /// let handle = pool.handle();
+    /// let answer = std::thread::spawn(move || handle.spawn(async { 42 }).unwrap()).join().unwrap();
+    /// assert_eq!(pool.run_until(answer), 42);
+    /// ```
+    pub fn handle(&self) -> Handle {
+        Handle::new(Arc::new(Remote(Arc::downgrade(&self.ready))))
+    }
+
+    /// Block the current thread until every spawned future has completed.
+    #[track_caller]
+    pub fn run(&mut self) {
+        crate::block_on(poll_fn(|cx| {
+            self.poll_ready(cx.waker(), false);
+            if self.is_idle() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        }))
+    }
+
+    /// Block the current thread until `fut` completes, running the spawned futures meanwhile.
+    ///
+    /// Spawned futures that haven't completed by then are left in the pool.
+    #[track_caller]
+    pub fn run_until<F: Future>(&mut self, fut: F) -> F::Output {
+        self.drive(fut)
+    }
+
+    // `run_until` through a shared reference, which is all `Executor::block_on` gets.
+    #[track_caller]
+    fn drive<F: Future>(&self, fut: F) -> F::Output {
+        let mut fut = std::pin::pin!(fut);
+        crate::block_on(poll_fn(|cx| {
+            let poll = enter::entered("the future given to LocalPool::run_until", || {
+                coop::budgeted(self.budget, || fut.as_mut().poll(cx))
+            });
+            if let Poll::Ready(output) = poll {
+                return Poll::Ready(output);
+            }
+            self.poll_ready(cx.waker(), false);
+            Poll::Pending
+        }))
+    }
+
+    /// Run spawned futures until none of them can make progress, without blocking.
+    pub fn run_until_stalled(&mut self) {
+        let waker = crate::noop_waker();
+        while self.poll_ready(&waker, false) > 0 || self.has_ready() {}
+    }
+
+    /// Run spawned futures until one of them completes or none can make progress, without
+    /// blocking. Returns whether one completed.
+    pub fn try_run_one(&mut self) -> bool {
+        let waker = crate::noop_waker();
+        loop {
+            if self.poll_ready(&waker, true) > 0 {
+                return true;
+            } else if !self.has_ready() {
+                return false;
+            }
+        }
+    }
+
+    /// List the pool's tasks that haven't completed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use pollster::{local::LocalPool, TaskState};
+    ///
+    /// let mut pool = LocalPool::new();
+    /// pool.spawner().spawn_local_named("idle", std::future::pending()).unwrap();
+    /// pool.run_until_stalled();
+    /// let tasks = pool.dump();
+    /// assert_eq!(tasks[0].name(), Some("idle"));
+    /// assert_eq!(tasks[0].state(), TaskState::Waiting);
+    /// assert_eq!(tasks[0].polls(), 1);
+    /// ```
+    pub fn dump(&self) -> Vec<TaskInfo> {
+        let tasks = self.tasks.borrow();
+        let mut infos: Vec<TaskInfo> = tasks
+            .entries
+            .iter()
+            .zip(&tasks.wakers)
+            .filter_map(|(entry, (task, _))| Some((entry.as_ref()?, task)))
+            .map(|(entry, task)| TaskInfo {
+                name: entry.name,
+                location: entry.location,
+                state: if task.queued.load(Ordering::Acquire) {
+                    TaskState::Scheduled
+                } else {
+                    TaskState::Waiting
+                },
+                polls: entry.polls,
+            })
+            .collect();
+        let not_adopted = |name, location| TaskInfo {
+            name,
+            location,
+            state: TaskState::Scheduled,
+            polls: 0,
+        };
+        infos.extend(self.incoming.borrow().iter().map(|incoming| not_adopted(incoming.name, incoming.location)));
+        let state = self.ready.state.lock().unwrap();
+        infos.extend(state.spawned.iter().map(|&(_, location)| not_adopted(None, location)));
+        infos
+    }
+
+    fn is_idle(&self) -> bool {
+        let tasks = self.tasks.borrow();
+        tasks.free.len() == tasks.entries.len()
+            && self.incoming.borrow().is_empty()
+            && self.ready.state.lock().unwrap().spawned.is_empty()
+    }
+
+    fn has_ready(&self) -> bool {
+        let state = self.ready.state.lock().unwrap();
+        !state.ids.is_empty() || !state.spawned.is_empty() || !self.incoming.borrow().is_empty()
+    }
+
+    // Move newly spawned futures into the pool, queueing them to be polled.
+    fn adopt(&self, tasks: &mut Tasks) {
+        let spawned = mem::take(&mut self.ready.state.lock().unwrap().spawned);
+        let mut incoming = mem::take(&mut *self.incoming.borrow_mut());
+        incoming.extend(spawned.into_iter().map(|(fut, location)| Incoming {
+            fut,
+            name: None,
+            location,
+        }));
+        for Incoming { fut, name, location } in incoming {
+            let id = tasks.free.pop().unwrap_or_else(|| {
+                tasks.entries.push(None);
+                tasks.entries.len() - 1
+            });
+            let generation = self.recycle_waker(tasks, id);
+            tasks.entries[id] = Some(Entry {
+                fut,
+                name,
+                location,
+                polls: 0,
+                generation,
+            });
+            self.ready.push((id, generation));
+        }
+    }
+
+    // Ready the waker for a new task in slot `id`, returning the task's generation. The slot's
+    // waker is reused unless clones of it are still around, since those would wake the new task.
+    fn recycle_waker(&self, tasks: &mut Tasks, id: usize) -> u32 {
+        if let Some((task, _)) = tasks.wakers.get(id) {
+            // Held only by the slab, as the `Arc` and the `Waker`.
+            if Arc::strong_count(task) == 2 {
+                let generation = task.generation.load(Ordering::Relaxed).wrapping_add(1);
+                task.generation.store(generation, Ordering::Release);
+                task.queued.store(true, Ordering::Release);
+                return generation;
+            }
+        }
+
+        let generation = tasks
+            .wakers
+            .get(id)
+            .map_or(0, |(task, _)| task.generation.load(Ordering::Relaxed).wrapping_add(1));
+        let task = Arc::new(TaskWaker {
+            id,
+            generation: AtomicU32::new(generation),
+            queued: AtomicBool::new(true),
+            ready: Arc::clone(&self.ready),
+        });
+        let waker = Waker::from(Arc::clone(&task));
+        if id < tasks.wakers.len() {
+            tasks.wakers[id] = (task, waker);
+        } else {
+            tasks.wakers.push((task, waker));
+            #[cfg(feature = "stats")]
+            crate::stats::add_waker_slots(1);
+        }
+        generation
+    }
+
+    // Poll every task woken so far once, returning how many completed. With `stop_early`, stops
+    // after the first completion.
+    fn poll_ready(&self, waker: &Waker, stop_early: bool) -> usize {
+        let mut tasks = self
+            .tasks
+            .try_borrow_mut()
+            .expect("a LocalPool can't be run from inside one of its own tasks");
+        let tasks = &mut *tasks;
+        self.adopt(tasks);
+        let mut unpolled = Unpolled {
+            ids: self.ready.take(waker),
+            ready: Arc::clone(&self.ready),
+        };
+        #[cfg(feature = "test-util")]
+        if let Some(shuffle) = &mut tasks.shuffle {
+            shuffle.shuffle(unpolled.ids.make_contiguous());
+        }
+        let mut completed = 0;
+        while let Some((id, generation)) = unpolled.ids.pop_front() {
+            let entry = match tasks.entries.get_mut(id) {
+                Some(Some(entry)) if entry.generation == generation => entry,
+                // Woken after it completed.
+                _ => continue,
+            };
+            let (task, waker) = &tasks.wakers[id];
+            task.queued.store(false, Ordering::Release);
+            entry.polls += 1;
+            let (budget, policy) = (self.budget, self.on_task_panic);
+            let poll = enter::entered("a LocalPool task", || {
+                executor::poll_task(policy, entry.name, || {
+                    coop::budgeted(budget, || entry.fut.as_mut().poll(&mut Context::from_waker(waker)))
+                })
+            });
+            let poll = poll.unwrap_or_else(|payload| {
+                // Dropped before unwinding so it isn't polled again if the pool is reused.
+                tasks.entries[id] = None;
+                tasks.free.push(id);
+                panic::resume_unwind(payload)
+            });
+            if poll.is_ready() {
+                tasks.entries[id] = None;
+                tasks.free.push(id);
+                completed += 1;
+                if stop_early {
+                    break;
+                }
+            }
+            self.adopt(tasks);
+        }
+        completed
+    }
+}
+
+// The tasks taken from the run queue that haven't been polled yet, put back when dropped, even by a
+// task's panic unwinding.
+struct Unpolled {
+    ids: VecDeque<TaskId>,
+    ready: Arc<ReadyQueue>,
+}
+
+impl Drop for Unpolled {
+    fn drop(&mut self) {
+        self.ready.restore(mem::take(&mut self.ids));
+    }
+}
+
+impl Default for LocalPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// `block_on` runs the pool's own futures alongside `fut`, like `run_until`; spawned futures go
+// through a handle, so they only run while the pool is driven.
+impl Executor for LocalPool {
+    #[track_caller]
+    fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        self.drive(fut)
+    }
+
+    #[track_caller]
+    fn spawn<F>(&self, fut: F) -> Result<JoinHandle<F::Output>, SpawnError>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.handle().spawn(fut)
+    }
+}
+
+impl Drop for LocalPool {
+    fn drop(&mut self) {
+        let spawned = {
+            let mut state = self.ready.state.lock().unwrap();
+            state.closed = true;
+            mem::take(&mut state.spawned)
+        };
+        drop(spawned);
+        #[cfg(feature = "stats")]
+        crate::stats::remove_waker_slots(self.tasks.get_mut().wakers.len());
+
+        #[cfg(feature = "test-util")]
+        if let (Some(seed), true) = (self.seed(), std::thread::panicking()) {
+            eprintln!("pollster: LocalPool task order was shuffled with POLLSTER_SEED={}", seed);
+        }
+    }
+}
+
+impl fmt::Debug for LocalPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tasks = self.tasks.borrow();
+        f.debug_struct("LocalPool")
+            .field("tasks", &(tasks.entries.len() - tasks.free.len()))
+            .finish_non_exhaustive()
+    }
+}
+
+// A xorshift64* generator, which is plenty for shuffling run queues.
+#[cfg(feature = "test-util")]
+struct Shuffle {
+    seed: u64,
+    state: u64,
+}
+
+#[cfg(feature = "test-util")]
+impl Shuffle {
+    fn new(seed: u64) -> Self {
+        // The state must be non-zero; mix the seed so nearby seeds diverge quickly.
+        let state = (seed ^ 0x9e37_79b9_7f4a_7c15).wrapping_mul(0xbf58_476d_1ce4_e5b9) | 1;
+        Self { seed, state }
+    }
+
+    fn random_seed() -> u64 {
+        use std::hash::{BuildHasher, Hasher};
+        std::collections::hash_map::RandomState::new().build_hasher().finish()
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn shuffle<T>(&mut self, ids: &mut [T]) {
+        for i in (1..ids.len()).rev() {
+            let j = (self.next() % (i as u64 + 1)) as usize;
+            ids.swap(i, j);
+        }
+    }
+}
+
+/// A handle for spawning futures onto a [`LocalPool`], returned by [`LocalPool::spawner`].
+#[derive(Clone)]
+pub struct LocalSpawner {
+    incoming: Weak<RefCell<Vec<Incoming>>>,
+}
+
+impl LocalSpawner {
+    /// Add a future to the pool. It first runs the next time the pool is run.
+    ///
+    /// Fails if the pool has been dropped.
+    #[track_caller]
+    pub fn spawn_local<F>(&self, fut: F) -> Result<(), SpawnError>
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        self.spawn(None, Box::pin(fut))
+    }
+
+    /// Like [`spawn_local`](LocalSpawner::spawn_local), but give the task a name, which is passed
+    /// to the [task panic hook](crate::set_task_panic_hook) and printed if the task panics.
+    #[track_caller]
+    pub fn spawn_local_named<F>(&self, name: &'static str, fut: F) -> Result<(), SpawnError>
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        self.spawn(Some(name), Box::pin(fut))
+    }
+
+    #[track_caller]
+    fn spawn(&self, name: Option<&'static str>, fut: LocalFuture) -> Result<(), SpawnError> {
+        let incoming = self.incoming.upgrade().ok_or_else(SpawnError::new)?;
+        incoming.borrow_mut().push(Incoming {
+            fut,
+            name,
+            location: Location::caller(),
+        });
+        #[cfg(feature = "stats")]
+        crate::stats::record_spawn();
+        Ok(())
+    }
+}
+
+impl fmt::Debug for LocalSpawner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalSpawner").finish_non_exhaustive()
+    }
+}
+
+/// Error returned by [`LocalSpawner::spawn_local`] and [`Handle::spawn`] when the executor has
+/// been dropped, and by [`Executor::spawn`](crate::Executor::spawn) on executors that can't spawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpawnError {
+    unsupported: bool,
+}
+
+impl SpawnError {
+    pub(crate) fn new() -> Self {
+        Self { unsupported: false }
+    }
+
+    pub(crate) fn unsupported() -> Self {
+        Self { unsupported: true }
+    }
+
+    /// Whether the executor doesn't support spawning at all, rather than having been dropped.
+    pub fn is_unsupported(&self) -> bool {
+        self.unsupported
+    }
+}
+
+impl fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.unsupported {
+            "executor doesn't support spawning"
+        } else {
+            "executor has been dropped"
+        })
+    }
+}
+
+impl Error for SpawnError {}
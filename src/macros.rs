@@ -0,0 +1,136 @@
+/// Block the current thread until the first of several futures completes, then evaluate the
+/// corresponding branch with the future's output bound to its pattern.
+///
+/// Each branch has the form `pattern = future => expression`. Branches are first polled in the
+/// order they are written, and after that only once their future has been woken. Once one
+/// completes, all of the futures are dropped before its expression is
+/// evaluated, so the expression runs in the caller's context (`return`, `?` and `break` work as
+/// usual). Patterns must be irrefutable.
+///
+/// # Example
+///
+/// ```
+/// let out = pollster::select! {
+///     a = std::future::pending::<u32>() => a,
+///     b = async { 2 } => b * 2,
+/// };
+/// assert_eq!(out, 4);
+/// ```
+#[macro_export]
+macro_rules! select {
+    ($($tokens:tt)*) => {
+        $crate::__select_inner!(@munch [] $($tokens)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __select_inner {
+    // Every step of the muncher introduces a fresh, hygienically distinct `__fut`/`__out` pair.
+    (@munch [$($done:tt)*] $pat:pat = $fut:expr => $body:expr $(, $($rest:tt)*)?) => {
+        $crate::__select_inner!(
+            @munch [$($done)* (__fut __out __index ($pat) ($fut) ($body))] $($($rest)*)?
+        )
+    };
+    (@munch [$(($fut_id:ident $out_id:ident $index_id:ident ($pat:pat) ($fut:expr) ($body:expr)))*]) => {{
+        let ($($out_id,)*) = {
+            let mut ready = $crate::__ReadyQueue::with_len(0);
+            $(
+                let mut $fut_id = ::std::pin::pin!($fut);
+                let $index_id = ready.add();
+                let mut $out_id = ::std::option::Option::None;
+            )*
+            $crate::block_on(::std::future::poll_fn(|cx| {
+                for index in ready.take(cx.waker()) {
+                    $(
+                        if index == $index_id {
+                            let cx = &mut ready.context(index);
+                            if let ::std::task::Poll::Ready(out) = ::std::future::Future::poll($fut_id.as_mut(), cx) {
+                                $out_id = ::std::option::Option::Some(out);
+                                return ::std::task::Poll::Ready(());
+                            }
+                        }
+                    )*
+                }
+                ::std::task::Poll::Pending
+            }));
+            ($($out_id,)*)
+        };
+        $(
+            if let ::std::option::Option::Some($pat) = $out_id { $body } else
+        )*
+        { ::std::unreachable!("select! branch pattern did not match the future's output") }
+    }};
+}
+
+/// Block the current thread until all of the given futures complete, returning a tuple of their
+/// outputs.
+///
+/// The futures are driven concurrently from the blocking loop. They are stored in place rather
+/// than boxed, and are first polled in the order they are written. After that, each one has a
+/// waker of its own and is only polled again once it has been woken.
+///
+/// # Example
+///
+/// ```
+/// let (a, b, c) = pollster::join!(async { 1 }, async { "two" }, async { 3.0 });
+/// assert_eq!((a, b, c), (1, "two", 3.0));
+/// ```
+#[macro_export]
+macro_rules! join {
+    ($($fut:expr),* $(,)?) => {
+        $crate::__join_inner!(@munch [] $($fut,)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __join_inner {
+    (@munch [$($done:tt)*] $fut:expr, $($rest:tt)*) => {
+        $crate::__join_inner!(@munch [$($done)* (__fut __out __index ($fut))] $($rest)*)
+    };
+    (@munch []) => {
+        ()
+    };
+    (@munch [$(($fut_id:ident $out_id:ident $index_id:ident ($fut:expr)))*]) => {{
+        let mut ready = $crate::__ReadyQueue::with_len(0);
+        $(
+            let mut $fut_id = ::std::pin::pin!($fut);
+            let $index_id = ready.add();
+            let mut $out_id = ::std::option::Option::None;
+        )*
+        $crate::block_on(::std::future::poll_fn(|cx| {
+            for index in ready.take(cx.waker()) {
+                $(
+                    if index == $index_id && $out_id.is_none() {
+                        let cx = &mut ready.context(index);
+                        if let ::std::task::Poll::Ready(out) = ::std::future::Future::poll($fut_id.as_mut(), cx) {
+                            $out_id = ::std::option::Option::Some(out);
+                        }
+                    }
+                )*
+            }
+            if $($out_id.is_some())&&* {
+                ::std::task::Poll::Ready(())
+            } else {
+                ::std::task::Poll::Pending
+            }
+        }));
+        ($($out_id.unwrap(),)*)
+    }};
+}
+
+/// Panic if blocking the current thread could deadlock; shorthand for
+/// [`assert_blocking_allowed`](crate::assert_blocking_allowed()).
+///
+/// # Example
+///
+/// ```
+/// pollster::assert_blocking_allowed!();
+/// ```
+#[macro_export]
+macro_rules! assert_blocking_allowed {
+    () => {
+        $crate::assert_blocking_allowed()
+    };
+}
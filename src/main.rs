@@ -3,13 +3,84 @@
 
 use std::{
     future::Future,
-    sync::{Arc, Condvar, Mutex},
-    task::{Context, Poll, Wake, Waker},
+    ptr,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    task::{Context, Poll, RawWaker, RawWakerVTable, Wake, Waker},
+    time::{Duration, Instant},
 };
 
 #[cfg(feature = "macro")]
 pub use pollster_macro::{main, test};
 
+pub use block_once::{AlreadyRun, BlockOnce};
+pub use enter::{assert_blocking_allowed, is_blocking_allowed};
+pub use error::Error;
+pub use executor::{
+    set_task_panic_hook, Executor, Handle, IdleContext, IdleDecision, PanicPolicy, Runner, TaskInfo, TaskState,
+};
+pub use join::{block_on_race, join_all_limited, race_ok, try_join, try_join_all};
+pub use join_set::{ArrayJoinSet, JoinNext};
+pub use parker::{Parker, WaitResult};
+pub use shutdown::shutdown;
+pub use slot::Slot;
+#[cfg(feature = "stats")]
+pub use stats::{stats, Stats};
+pub use unblock::{unblock, Unblock};
+
+// Used by the expansions of `join!` and `select!`.
+#[doc(hidden)]
+pub use ready::ReadyQueue as __ReadyQueue;
+
+pub mod bench;
+mod block_once;
+pub mod compat;
+mod coop;
+mod enter;
+mod error;
+mod executor;
+pub mod ffi;
+pub mod fs;
+pub mod future;
+#[cfg(feature = "io")]
+pub mod io;
+#[cfg(feature = "stream")]
+pub mod iter;
+mod join;
+mod join_set;
+pub mod local;
+mod macros;
+pub mod net;
+mod oneshot;
+mod parker;
+mod probe;
+pub mod pool;
+pub mod process;
+mod profile;
+mod ready;
+mod shutdown;
+#[cfg(all(feature = "signal", any(unix, windows)))]
+pub mod signal;
+mod slot;
+#[cfg(feature = "stats")]
+mod stats;
+#[cfg(feature = "stream")]
+pub mod stream;
+pub mod sync;
+pub mod task;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+pub mod time;
+#[cfg(feature = "trace")]
+pub mod trace;
+mod unblock;
+
+#[cfg(any(feature = "debug-waker", feature = "trace"))]
+mod instrument;
+#[cfg(all(windows, feature = "windows-message-pump"))]
+mod msgpump;
+#[cfg(all(target_os = "macos", feature = "macos-runloop"))]
+mod runloop;
+
 pub trait FutureExt: Future {
     /// ```
     /// use pollster::FutureExt as _;
@@ -18,6 +89,7 @@ pub trait FutureExt: Future {
     ///
     /// let result = my_fut.block_on();
     /// ```
+    #[track_caller]
     fn block_on(self) -> Self::Output where Self: Sized { block_on(self) }
 }
 
@@ -32,18 +104,101 @@ enum SignalState {
 struct Signal {
     state: Mutex<SignalState>,
     cond: Condvar,
+    #[cfg(all(target_os = "macos", feature = "macos-runloop"))]
+    run_loop: Option<runloop::MainRunLoop>,
+    #[cfg(all(windows, feature = "windows-message-pump"))]
+    message_pump: Option<msgpump::MessagePump>,
+    #[cfg(any(feature = "debug-waker", feature = "trace"))]
+    instrument: instrument::Instrument,
+    // Woken alongside the signal, for `block_on_with_waker`.
+    external: Option<Waker>,
 }
 
 impl Signal {
-    fn new() -> Self {
+    // A signal not yet tied to any thread's run loop or message queue.
+    fn detached(external: Option<Waker>) -> Self {
         Self {
             state: Mutex::new(SignalState::Empty),
             cond: Condvar::new(),
+            external,
+            #[cfg(all(target_os = "macos", feature = "macos-runloop"))]
+            run_loop: None,
+            #[cfg(all(windows, feature = "windows-message-pump"))]
+            message_pump: None,
+            #[cfg(any(feature = "debug-waker", feature = "trace"))]
+            instrument: instrument::Instrument::default(),
+        }
+    }
+
+    // Pump the current thread's run loop or message queue while waiting, if it has one.
+    fn attach_current_thread(&mut self) {
+        #[cfg(all(target_os = "macos", feature = "macos-runloop"))]
+        {
+            self.run_loop = runloop::MainRunLoop::current();
+        }
+        #[cfg(all(windows, feature = "windows-message-pump"))]
+        {
+            self.message_pump = msgpump::MessagePump::current();
+        }
+    }
+
+    fn current() -> Arc<Self> {
+        Self::with_external(None)
+    }
+
+    fn with_external(external: Option<Waker>) -> Arc<Self> {
+        let mut signal = Self::detached(external);
+        signal.attach_current_thread();
+        Arc::new(signal)
+    }
+
+    // `block_on` keeps two references to the signal: its own and the one inside its waker. If those
+    // are the only ones left and no notification is pending, nothing can ever wake us, so we
+    // return `false` instead of parking forever.
+    fn orphaned(self: &Arc<Self>) -> bool {
+        Arc::strong_count(self) <= 2
+    }
+
+    // The state is a plain enum that is never left half-updated, so a panic elsewhere while the
+    // lock was held can't have broken it. Recovering from poisoning means such a panic, say on a
+    // thread in the middle of waking us, can't cascade into this `block_on` call.
+    fn lock(&self) -> MutexGuard<'_, SignalState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn wait(self: &Arc<Self>) -> bool {
+        #[cfg(all(target_os = "macos", feature = "macos-runloop"))]
+        if let Some(run_loop) = &self.run_loop {
+            return self.pump(|| run_loop.run());
+        }
+        #[cfg(all(windows, feature = "windows-message-pump"))]
+        if let Some(message_pump) = &self.message_pump {
+            return self.pump(|| message_pump.run());
+        }
+
+        let mut state = self.lock();
+        match *state {
+            SignalState::Notified => *state = SignalState::Empty,
+            SignalState::Waiting => {
+                unreachable!("Multiple threads waiting on the same signal: Open a bug report!");
+            }
+            SignalState::Empty if self.orphaned() => return false,
+            SignalState::Empty => {
+                *state = SignalState::Waiting;
+                while let SignalState::Waiting = *state {
+                    state = self.cond.wait(state).unwrap_or_else(PoisonError::into_inner);
+                }
+            }
         }
+        true
     }
 
-    fn wait(&self) {
-        let mut state = self.state.lock().unwrap();
+    // Park on the condvar for at most `timeout`, or until notified if that's too far out to
+    // represent. This never pumps the run loop or message queue: whoever asked for a bounded wait
+    // is running the thread's event loop themselves.
+    fn wait_timeout(&self, timeout: Duration) {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut state = self.lock();
         match *state {
             SignalState::Notified => *state = SignalState::Empty,
             SignalState::Waiting => {
@@ -52,14 +207,48 @@ impl Signal {
             SignalState::Empty => {
                 *state = SignalState::Waiting;
                 while let SignalState::Waiting = *state {
-                    state = self.cond.wait(state).unwrap();
+                    let deadline = match deadline {
+                        Some(deadline) => deadline,
+                        None => {
+                            state = self.cond.wait(state).unwrap_or_else(PoisonError::into_inner);
+                            continue;
+                        }
+                    };
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining == Duration::ZERO {
+                        *state = SignalState::Empty;
+                        break;
+                    }
+                    state = self.cond.wait_timeout(state, remaining).unwrap_or_else(PoisonError::into_inner).0;
                 }
             }
         }
     }
 
+    // On the macOS main thread and on Windows GUI threads we never park on the condvar. Instead the
+    // main run loop (and with it the main dispatch queue) or the thread's message queue keeps
+    // running until `notify` interrupts it.
+    #[cfg(any(
+        all(target_os = "macos", feature = "macos-runloop"),
+        all(windows, feature = "windows-message-pump"),
+    ))]
+    fn pump(self: &Arc<Self>, run: impl Fn()) -> bool {
+        loop {
+            let mut state = self.lock();
+            if let SignalState::Notified = *state {
+                *state = SignalState::Empty;
+                return true;
+            } else if self.orphaned() {
+                return false;
+            }
+            drop(state);
+            run();
+        }
+    }
+
     fn notify(&self) {
-        let mut state = self.state.lock().unwrap();
+        profile::wake_marker();
+        let mut state = self.lock();
         match *state {
             SignalState::Notified => {}
             SignalState::Empty => *state = SignalState::Notified,
@@ -68,6 +257,17 @@ impl Signal {
                 self.cond.notify_one();
             }
         }
+        #[cfg(all(target_os = "macos", feature = "macos-runloop"))]
+        if let Some(run_loop) = &self.run_loop {
+            run_loop.wake();
+        }
+        #[cfg(all(windows, feature = "windows-message-pump"))]
+        if let Some(message_pump) = &self.message_pump {
+            message_pump.wake();
+        }
+        if let Some(external) = &self.external {
+            external.wake_by_ref();
+        }
     }
 }
 
@@ -77,23 +277,343 @@ impl Wake for Signal {
     }
 }
 
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(ptr::null(), &NOOP_VTABLE)
+    }
+    fn noop(_: *const ()) {}
+
+    const NOOP_VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+    unsafe { Waker::from_raw(RawWaker::new(ptr::null(), &NOOP_VTABLE)) }
+}
+
+// A readable name for the future type in diagnostics: module paths are stripped from every type
+// (keeping the parent of closures and async blocks, which would otherwise be anonymous) and overly
+// long names are cut short.
+fn type_label<F>() -> String {
+    const MAX_LEN: usize = 160;
+
+    fn push_path(label: &mut String, path: &str) {
+        let mut segments = path.rsplit("::");
+        let last = segments.next().unwrap_or_default();
+        if let (true, Some(parent)) = (last.starts_with('{'), segments.next()) {
+            label.push_str(parent);
+            label.push_str("::");
+        }
+        label.push_str(last);
+    }
+
+    let full = std::any::type_name::<F>();
+    let mut label = String::with_capacity(full.len());
+    let mut start = 0;
+    for (i, c) in full.char_indices() {
+        if "<>,()[]; &*".contains(c) {
+            push_path(&mut label, &full[start..i]);
+            label.push(c);
+            start = i + c.len_utf8();
+        }
+    }
+    push_path(&mut label, &full[start..]);
+
+    if label.chars().count() > MAX_LEN {
+        label = label.chars().take(MAX_LEN).chain("...".chars()).collect();
+    }
+    label
+}
+
 /// Block the thread until the future is ready.
 ///
+/// With the `macos-runloop` feature enabled, calling this on the macOS main thread keeps the main
+/// run loop (and therefore the main dispatch queue) running while the future is pending, so work
+/// that AppKit or Metal schedules onto the main thread can still make progress.
+///
+/// Likewise, with the `windows-message-pump` feature enabled, calling this on a Windows thread that
+/// owns windows keeps dispatching that thread's window messages while the future is pending, so
+/// the application doesn't stop responding. A `WM_QUIT` message is left in the queue for the
+/// thread's own message loop.
+///
+/// The future is first polled with a waker that does nothing, so that futures which are already
+/// complete cost no allocation or synchronization. If it is still pending, it is polled again
+/// with a real waker before the thread is parked.
+///
+/// With the `profile-puffin` or `profile-tracy` feature enabled, every poll and every park is
+/// recorded as a profiler scope tagged with the caller's location, and wakes are recorded as
+/// markers.
+///
+/// With the `debug-waker` feature enabled, the waker handed to the future reports suspicious usage
+/// on stderr: waker clones still alive once the future completes, wakes arriving after completion,
+/// and floods of wakes between two polls.
+///
+/// With the `trace` feature enabled, every poll, park, wake and waker clone or drop is recorded in
+/// a process-wide timeline that can be read with [`trace::snapshot`]. The timeline is printed to
+/// stderr before the deadlock panic described below.
+///
+/// # Panics
+///
+/// Panics if the future returns [`Poll::Pending`] after dropping every clone of its waker without
+/// waking it, since it could then never complete. The message names the future's type and the
+/// location `block_on` was called from.
+///
+/// The check counts the live clones of the waker. A future that keeps a clone anywhere, say in a
+/// callback registry or on another thread, is waited on as usual, however long the wake takes.
+/// Only a future that has let go of every clone is reported.
+///
+/// Also panics if the future needs to wait on a target whose threads can't block at all, such as
+/// `wasm32` without the `atomics` target feature (the browser main thread). Futures that complete
+/// without waiting still work there. Use [`can_block`] or [`try_block_on`] to take another route,
+/// like `wasm_bindgen_futures::spawn_local`, on such targets.
+///
 /// # Example
 ///
 /// ```
 /// let my_fut = async {};
 /// let result = pollster::block_on(my_fut);
 /// ```
-pub fn block_on<F: Future>(mut fut: F) -> F::Output {
-    let mut fut = unsafe { std::pin::Pin::new_unchecked(&mut fut) };
-    let signal = Arc::new(Signal::new());
-    let waker = Waker::from(Arc::clone(&signal));
+///
+/// A waker stored away and woken much later, from another thread, keeps the call waiting:
+///
+/// ```
+/// use std::{
+///     future::poll_fn,
+///     sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex},
+///     task::{Poll, Waker},
+///     thread,
+///     time::Duration,
+/// };
+///
+/// let done = Arc::new(AtomicBool::new(false));
+/// let stored = Arc::new(Mutex::new(None::<Waker>));
+/// let completer = thread::spawn({
+///     let (done, stored) = (Arc::clone(&done), Arc::clone(&stored));
+///     move || {
+///         thread::sleep(Duration::from_millis(50));
+///         done.store(true, Ordering::SeqCst);
+///         if let Some(waker) = stored.lock().unwrap().take() {
+///             waker.wake();
+///         }
+///     }
+/// });
+///
+/// pollster::block_on(poll_fn(|cx| {
+///     if done.load(Ordering::SeqCst) {
+///         return Poll::Ready(());
+///     }
+///     *stored.lock().unwrap() = Some(cx.waker().clone());
+///     Poll::Pending
+/// }));
+/// completer.join().unwrap();
+/// ```
+///
+/// A future that drops its waker without waking it could never complete, so it is reported:
+///
+/// ```should_panic
+/// pollster::block_on(std::future::poll_fn(|_| std::task::Poll::<()>::Pending));
+/// ```
+#[track_caller]
+pub fn block_on<F: Future>(fut: F) -> F::Output {
+    block_on_inner(fut, None, Signal::current, None)
+}
+
+/// Block the thread until the future is ready, like [`block_on`], attributing the call to `name`.
+///
+/// The name appears next to the caller's location wherever the call is reported: profiler scopes,
+/// [`trace`] events, `debug-waker` reports and the deadlock panic message. With many `block_on`
+/// call sites, names make it practical to tell which one stalled.
+///
+/// # Example
+///
+/// ```
+/// let pixels = pollster::block_on_named("texture-upload", async { vec![0u8; 16] });
+/// assert_eq!(pixels.len(), 16);
+/// ```
+#[track_caller]
+pub fn block_on_named<F: Future>(name: &'static str, fut: F) -> F::Output {
+    block_on_inner(fut, Some(name), Signal::current, None)
+}
+
+/// Block the thread until the future is ready, like [`block_on`], additionally waking `external`
+/// whenever the future's waker is woken.
+///
+/// This lets wakeups inside the blocked future also nudge an outer event loop that embeds it.
+///
+/// # Example
+///
+/// ```
+/// use std::{sync::{atomic::{AtomicUsize, Ordering}, Arc}, task::{Wake, Waker}, time::Duration};
+///
+/// struct Count(AtomicUsize);
+///
+/// impl Wake for Count {
+///     fn wake(self: Arc<Self>) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let count = Arc::new(Count(AtomicUsize::new(0)));
+/// let external = Waker::from(Arc::clone(&count));
+/// pollster::block_on_with_waker(pollster::time::sleep(Duration::from_millis(1)), external);
+/// assert_eq!(count.0.load(Ordering::Relaxed), 1);
+/// ```
+#[track_caller]
+pub fn block_on_with_waker<F: Future>(fut: F, external: Waker) -> F::Output {
+    block_on_inner(fut, None, || Signal::with_external(Some(external)), None)
+}
+
+/// Whether [`block_on`] can park the current thread on this target.
+///
+/// This is `false` on `wasm32` without the `atomics` target feature, where there is no way to wait
+/// for a wakeup. Libraries can check it to fall back to spawning the future instead.
+///
+/// # Example
+///
+/// ```
+/// if pollster::can_block() {
+///     pollster::block_on(async {});
+/// }
+/// ```
+pub const fn can_block() -> bool {
+    !cfg!(all(target_arch = "wasm32", not(target_feature = "atomics")))
+}
+
+/// Block the thread until the future is ready, like [`block_on`], or hand the future back unpolled
+/// if this target can't block (see [`can_block`]).
+///
+/// # Example
+///
+/// ```
+/// match pollster::try_block_on(async { 7 }) {
+///     Ok(value) => assert_eq!(value, 7),
+///     Err(fut) => drop(fut), // e.g. `wasm_bindgen_futures::spawn_local(fut)`
+/// }
+/// ```
+#[track_caller]
+pub fn try_block_on<F: Future>(fut: F) -> Result<F::Output, F> {
+    if can_block() {
+        Ok(block_on(fut))
+    } else {
+        Err(fut)
+    }
+}
+
+type OnIdle<'a> = &'a mut (dyn FnMut(IdleContext) -> IdleDecision + Send);
+
+#[track_caller]
+fn block_on_inner<F: Future>(
+    fut: F,
+    name: Option<&'static str>,
+    signal: impl FnOnce() -> Arc<Signal>,
+    mut on_idle: Option<OnIdle<'_>>,
+) -> F::Output {
+    let probe = probe::Probe::new(probe::Site {
+        location: std::panic::Location::caller(),
+        name,
+    });
+    let mut fut = std::pin::pin!(fut);
+    let poll = probe.poll(None, || fut.as_mut().poll(&mut Context::from_waker(&noop_waker())));
+    if let Poll::Ready(item) = poll {
+        return item;
+    }
+
+    let signal = signal();
+    let waker = probe.waker::<F>(&signal);
     let mut context = Context::from_waker(&waker);
+    // `Instant::now` panics on some targets that can't block, so only call it when it's needed.
+    let start = on_idle.as_ref().map(|_| Instant::now());
+    let mut polls = 1;
     loop {
-        match fut.as_mut().poll(&mut context) {
-            Poll::Pending => signal.wait(),
+        polls += 1;
+        match probe.poll(Some(&signal), || fut.as_mut().poll(&mut context)) {
+            Poll::Pending => {
+                time::check_deadlines(&probe.site(), type_label::<F>);
+                let decision = on_idle.as_mut().map_or(IdleDecision::Park, |on_idle| {
+                    on_idle(IdleContext {
+                        polls,
+                        elapsed: start.map_or(Duration::ZERO, |start| start.elapsed()),
+                    })
+                });
+                match decision {
+                    IdleDecision::Poll => continue,
+                    IdleDecision::ParkTimeout(timeout) => {
+                        let _watched = time::WatchParked::new(&signal);
+                        probe.park(|| {
+                            signal.wait_timeout(timeout);
+                            true
+                        });
+                        continue;
+                    }
+                    IdleDecision::Park => {}
+                }
+                if !can_block() {
+                    panic!(
+                        "block_on at {}: `{}` is pending, but this target can't block the thread",
+                        probe.site(),
+                        type_label::<F>(),
+                    );
+                }
+                let watched = time::WatchParked::new(&signal);
+                let woken = probe.park(|| signal.wait());
+                drop(watched);
+                if !woken {
+                    probe.deadlocked();
+                    panic!(
+                        "block_on deadlocked at {}: `{}` is pending but dropped its waker without waking it",
+                        probe.site(),
+                        type_label::<F>(),
+                    );
+                }
+            }
             Poll::Ready(item) => break item,
         }
     }
-}
\ No newline at end of file
+}
+
+/// Block the thread until a boxed future is ready, like [`block_on`].
+///
+/// This accepts unsized futures, such as the `Box<dyn Future>` trait objects handed out by plugin
+/// interfaces, which can't be passed to `block_on` directly unless they are [`Unpin`]. The box is
+/// pinned in place, without moving the future. Futures that are already pinned, like
+/// `Pin<Box<dyn Future>>`, can be passed to `block_on` as they are.
+///
+/// # Example
+///
+/// ```
+/// use std::future::Future;
+///
+/// fn plugin_task() -> Box<dyn Future<Output = &'static str>> {
+///     Box::new(async { "loaded" })
+/// }
+///
+/// assert_eq!(pollster::block_on_boxed(plugin_task()), "loaded");
+/// ```
+#[track_caller]
+pub fn block_on_boxed<F: Future + ?Sized>(fut: Box<F>) -> F::Output {
+    block_on(Box::into_pin(fut))
+}
+
+/// Block the thread until the future is ready, like [`block_on`], but only accept futures that are
+/// [`Send`].
+///
+/// This keeps layers that currently block free of accidental `!Send` captures, so their futures
+/// stay portable to multi-threaded executors.
+///
+/// # Example
+///
+/// ```
+/// let result = pollster::block_on_send(async { 1 + 1 });
+/// assert_eq!(result, 2);
+/// ```
+///
+/// Futures holding `!Send` values across an `.await` are rejected:
+///
+/// ```compile_fail
+/// let rc = std::rc::Rc::new(1);
+/// pollster::block_on_send(async move {
+///     std::future::ready(()).await;
+///     *rc
+/// });
+/// ```
+#[track_caller]
+pub fn block_on_send<F: Future + Send>(fut: F) -> F::Output {
+    block_on(fut)
+}
@@ -0,0 +1,129 @@
+use std::{
+    os::raw::c_void,
+    ptr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+type Bool = i32;
+type Handle = *mut c_void;
+type Hwnd = *mut c_void;
+
+const INFINITE: u32 = 0xFFFF_FFFF;
+const QS_ALLINPUT: u32 = 0x04FF;
+const MWMO_INPUTAVAILABLE: u32 = 0x0004;
+const WAIT_OBJECT_0: u32 = 0;
+const PM_REMOVE: u32 = 0x0001;
+const WM_QUIT: u32 = 0x0012;
+
+#[repr(C)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[repr(C)]
+struct Msg {
+    hwnd: Hwnd,
+    message: u32,
+    w_param: usize,
+    l_param: isize,
+    time: u32,
+    pt: Point,
+}
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn CreateEventW(attributes: *mut c_void, manual_reset: Bool, initial_state: Bool, name: *const u16) -> Handle;
+    fn SetEvent(event: Handle) -> Bool;
+    fn CloseHandle(handle: Handle) -> Bool;
+    fn WaitForSingleObject(handle: Handle, milliseconds: u32) -> u32;
+}
+
+#[link(name = "user32")]
+extern "system" {
+    fn IsGUIThread(convert: Bool) -> Bool;
+    fn MsgWaitForMultipleObjectsEx(
+        count: u32,
+        handles: *const Handle,
+        milliseconds: u32,
+        wake_mask: u32,
+        flags: u32,
+    ) -> u32;
+    fn PeekMessageW(msg: *mut Msg, hwnd: Hwnd, filter_min: u32, filter_max: u32, remove: u32) -> Bool;
+    fn TranslateMessage(msg: *const Msg) -> Bool;
+    fn DispatchMessageW(msg: *const Msg) -> isize;
+    fn PostQuitMessage(exit_code: i32);
+}
+
+/// An event the blocked GUI thread waits on alongside its message queue, used to interrupt
+/// `MessagePump::run` from any thread.
+pub(crate) struct MessagePump {
+    event: Handle,
+    // Set once `WM_QUIT` has been seen and re-posted for the thread's own message loop; from then
+    // on we stop dispatching, since the queue would report the quit again straight away.
+    quitting: AtomicBool,
+}
+
+// Events can be signalled from any thread.
+unsafe impl Send for MessagePump {}
+unsafe impl Sync for MessagePump {}
+
+impl MessagePump {
+    /// Returns `None` unless called on a GUI thread.
+    pub(crate) fn current() -> Option<Self> {
+        if unsafe { IsGUIThread(0) } == 0 {
+            return None;
+        }
+        let event = unsafe { CreateEventW(ptr::null_mut(), 0, 0, ptr::null()) };
+        if event.is_null() {
+            return None;
+        }
+        Some(Self {
+            event,
+            quitting: AtomicBool::new(false),
+        })
+    }
+
+    /// Wait until `wake` is called or window messages arrive, dispatching the messages.
+    pub(crate) fn run(&self) {
+        if self.quitting.load(Ordering::Relaxed) {
+            unsafe { WaitForSingleObject(self.event, INFINITE) };
+            return;
+        }
+
+        let woken = unsafe { MsgWaitForMultipleObjectsEx(1, &self.event, INFINITE, QS_ALLINPUT, MWMO_INPUTAVAILABLE) };
+        if woken == WAIT_OBJECT_0 {
+            return;
+        }
+        let mut msg = Msg {
+            hwnd: ptr::null_mut(),
+            message: 0,
+            w_param: 0,
+            l_param: 0,
+            time: 0,
+            pt: Point { x: 0, y: 0 },
+        };
+        while unsafe { PeekMessageW(&mut msg, ptr::null_mut(), 0, 0, PM_REMOVE) } != 0 {
+            if msg.message == WM_QUIT {
+                unsafe { PostQuitMessage(msg.w_param as i32) };
+                self.quitting.store(true, Ordering::Relaxed);
+                return;
+            }
+            unsafe {
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        }
+    }
+
+    /// Make a concurrent or future call to `run` return.
+    pub(crate) fn wake(&self) {
+        unsafe { SetEvent(self.event) };
+    }
+}
+
+impl Drop for MessagePump {
+    fn drop(&mut self) {
+        unsafe { CloseHandle(self.event) };
+    }
+}
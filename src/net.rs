@@ -0,0 +1,27 @@
+//! Asynchronous networking helpers, run on the [`unblock`](crate::unblock) pool.
+
+use crate::unblock;
+use std::{
+    io,
+    net::{SocketAddr, ToSocketAddrs},
+    vec,
+};
+
+/// Resolve a `host:port` string to socket addresses, like [`ToSocketAddrs`] on a `&str`.
+///
+/// The system resolver blocks, so lookups run on the blocking pool. Addresses that are already
+/// numeric, like `"127.0.0.1:80"` or `"[::1]:443"`, are parsed in place without a lookup.
+///
+/// # Example
+///
+/// ```
+/// let addrs: Vec<_> = pollster::block_on(pollster::net::lookup_host("localhost:8080")).unwrap().collect();
+/// assert!(addrs.iter().all(|addr| addr.port() == 8080 && addr.ip().is_loopback()));
+/// ```
+pub async fn lookup_host(host: impl AsRef<str>) -> io::Result<vec::IntoIter<SocketAddr>> {
+    if let Ok(addr) = host.as_ref().parse::<SocketAddr>() {
+        return Ok(vec![addr].into_iter());
+    }
+    let host = host.as_ref().to_owned();
+    unblock(move || host.to_socket_addrs()).await
+}
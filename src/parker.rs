@@ -0,0 +1,125 @@
+use std::{
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    task::{Wake, Waker},
+    time::{Duration, Instant},
+};
+
+struct Inner {
+    notified: Mutex<bool>,
+    cond: Condvar,
+}
+
+impl Inner {
+    // A bool can't be left inconsistent, so poisoning is ignored.
+    fn lock(&self) -> MutexGuard<'_, bool> {
+        self.notified.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Wake for Inner {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        *self.lock() = true;
+        self.cond.notify_one();
+    }
+}
+
+/// Parks the current thread until woken, for writing custom poll loops.
+///
+/// Wakes are never lost: a wake that arrives before the thread parks makes the next park return
+/// straight away. Several wakes before a park are coalesced into one.
+///
+/// # Example
+///
+/// ```
+/// use std::{future::Future, pin::Pin, task::{Context, Poll}, time::Duration};
+/// use pollster::{Parker, WaitResult};
+///
+/// let parker = Parker::new();
+/// let waker = parker.waker();
+/// let mut fut = Box::pin(pollster::time::sleep(Duration::from_millis(5)));
+/// loop {
+///     if let Poll::Ready(()) = fut.as_mut().poll(&mut Context::from_waker(&waker)) {
+///         break;
+///     }
+///     if parker.wait_timeout(Duration::from_secs(10)) == WaitResult::TimedOut {
+///         panic!("timer never fired");
+///     }
+/// }
+/// ```
+pub struct Parker {
+    inner: Arc<Inner>,
+}
+
+/// How a bounded wait on a [`Parker`] ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitResult {
+    /// The parker was woken.
+    Notified,
+    /// The timeout elapsed first.
+    TimedOut,
+}
+
+impl Parker {
+    /// Create a parker that hasn't been woken.
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                notified: Mutex::new(false),
+                cond: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Get a waker that wakes this parker. It can be sent to and woken from any thread.
+    pub fn waker(&self) -> Waker {
+        Waker::from(Arc::clone(&self.inner))
+    }
+
+    /// Block the current thread until woken.
+    pub fn park(&self) {
+        let mut notified = self.inner.lock();
+        // Condvars may wake spuriously, so only a recorded wake counts.
+        while !*notified {
+            notified = self.inner.cond.wait(notified).unwrap_or_else(PoisonError::into_inner);
+        }
+        *notified = false;
+    }
+
+    /// Block the current thread until woken or until `timeout` has elapsed.
+    pub fn wait_timeout(&self, timeout: Duration) -> WaitResult {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.wait_deadline(deadline),
+            // Too far in the future to represent, so it never elapses.
+            None => {
+                self.park();
+                WaitResult::Notified
+            }
+        }
+    }
+
+    /// Block the current thread until woken or until `deadline` has passed.
+    pub fn wait_deadline(&self, deadline: Instant) -> WaitResult {
+        let mut notified = self.inner.lock();
+        while !*notified {
+            // Recompute after every wakeup, spurious or not, rather than waiting the full timeout
+            // again.
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::ZERO {
+                return WaitResult::TimedOut;
+            }
+            notified = self.inner.cond.wait_timeout(notified, remaining).unwrap_or_else(PoisonError::into_inner).0;
+        }
+        *notified = false;
+        WaitResult::Notified
+    }
+}
+
+impl Default for Parker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
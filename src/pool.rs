@@ -0,0 +1,934 @@
+//! A pool of worker threads that run spawned futures to completion.
+
+use crate::{
+    executor::{self, Executor, Handle, PanicPolicy, SendFuture, Spawn, TaskInfo, TaskState},
+    local::SpawnError,
+    oneshot,
+};
+use std::{
+    any::Any,
+    collections::{HashMap, VecDeque},
+    fmt,
+    future::Future,
+    io,
+    marker::PhantomData,
+    mem::{self, ManuallyDrop},
+    panic::{self, AssertUnwindSafe, Location},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering},
+        Arc, Condvar, Mutex, Weak,
+    },
+    task::{Context, Poll, Wake, Waker},
+    thread,
+};
+
+type Hook = Arc<dyn Fn(usize) + Send + Sync>;
+
+// Task states. A task is in the queue exactly when it is `SCHEDULED`.
+const IDLE: u8 = 0;
+const SCHEDULED: u8 = 1;
+const RUNNING: u8 = 2;
+// Woken while running, so it must be polled again.
+const NOTIFIED: u8 = 3;
+const DONE: u8 = 4;
+
+struct Queue {
+    tasks: VecDeque<Arc<Task>>,
+    // Live `ThreadPool` handles. Once there are none, workers exit as soon as the queue is empty.
+    handles: usize,
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    cond: Condvar,
+    policy: PanicPolicy,
+    // Every task that hasn't been dropped yet, for `dump`.
+    tasks: Mutex<HashMap<u64, Weak<Task>>>,
+    next_id: AtomicU64,
+}
+
+impl Shared {
+    fn push(&self, task: Arc<Task>) {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.handles == 0 {
+            // Nobody will run it any more, so drop the future instead of leaking it in the queue.
+            drop(queue);
+            task.state.store(DONE, Ordering::Release);
+            *task.future.lock().unwrap() = None;
+            return;
+        }
+        queue.tasks.push_back(task);
+        self.cond.notify_one();
+    }
+
+    fn dump(&self) -> Vec<TaskInfo> {
+        // Upgraded under the lock but dropped outside it, since dropping the last reference to a
+        // task takes the lock again.
+        let tasks: Vec<Arc<Task>> = self.tasks.lock().unwrap().values().filter_map(Weak::upgrade).collect();
+        let mut infos: Vec<(u64, TaskInfo)> = tasks
+            .iter()
+            .filter_map(|task| {
+                let state = match task.state.load(Ordering::Acquire) {
+                    IDLE => TaskState::Waiting,
+                    SCHEDULED => TaskState::Scheduled,
+                    RUNNING | NOTIFIED => TaskState::Running,
+                    _ => return None,
+                };
+                let info = TaskInfo {
+                    name: task.name,
+                    location: task.location,
+                    state,
+                    polls: task.polls.load(Ordering::Relaxed),
+                };
+                Some((task.id, info))
+            })
+            .collect();
+        infos.sort_by_key(|(id, _)| *id);
+        infos.into_iter().map(|(_, info)| info).collect()
+    }
+
+    fn next(&self) -> Option<Arc<Task>> {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            if let Some(task) = queue.tasks.pop_front() {
+                return Some(task);
+            } else if queue.handles == 0 {
+                return None;
+            }
+            queue = self.cond.wait(queue).unwrap();
+        }
+    }
+
+    fn work(&self) {
+        while let Some(task) = self.next() {
+            task.run();
+        }
+    }
+}
+
+struct Task {
+    id: u64,
+    state: AtomicU8,
+    future: Mutex<Option<SendFuture>>,
+    name: Option<&'static str>,
+    location: &'static Location<'static>,
+    polls: AtomicU64,
+    pool: Arc<Shared>,
+}
+
+impl Task {
+    fn run(self: Arc<Self>) {
+        self.state.store(RUNNING, Ordering::Release);
+        self.polls.fetch_add(1, Ordering::Relaxed);
+        let waker = Waker::from(Arc::clone(&self));
+        let mut slot = self.future.lock().unwrap();
+        let fut = match slot.as_mut() {
+            Some(fut) => fut,
+            None => return,
+        };
+        // A panic that propagates this far has no handle to go to, so it only ends this task.
+        let poll = executor::poll_task(self.pool.policy, self.name, || {
+            fut.as_mut().poll(&mut Context::from_waker(&waker))
+        });
+        if let Ok(Poll::Pending) = poll {
+            if self.state.compare_exchange(RUNNING, IDLE, Ordering::AcqRel, Ordering::Acquire).is_err() {
+                self.state.store(SCHEDULED, Ordering::Release);
+                drop(slot);
+                let pool = Arc::clone(&self.pool);
+                pool.push(self);
+            }
+        } else {
+            *slot = None;
+            self.state.store(DONE, Ordering::Release);
+        }
+    }
+}
+
+impl Drop for Task {
+    fn drop(&mut self) {
+        self.pool.tasks.lock().unwrap().remove(&self.id);
+    }
+}
+
+impl Wake for Task {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let mut state = self.state.load(Ordering::Acquire);
+        loop {
+            let next = match state {
+                IDLE => SCHEDULED,
+                RUNNING => NOTIFIED,
+                _ => return,
+            };
+            match self.state.compare_exchange_weak(state, next, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) if next == SCHEDULED => return self.pool.push(Arc::clone(self)),
+                Ok(_) => return,
+                Err(actual) => state = actual,
+            }
+        }
+    }
+}
+
+/// A pool of worker threads running spawned futures.
+///
+/// Cloning the pool creates another handle to the same workers. Once every handle is dropped, the
+/// workers finish the tasks that are ready to run and exit; tasks still waiting at that point are
+/// dropped when next woken.
+///
+/// # Example
+///
+/// ```
+/// use pollster::pool::ThreadPool;
+///
+/// let pool = ThreadPool::new().unwrap();
+/// let handles: Vec<_> = (1..=4).map(|i| pool.spawn(async move { i * i })).collect();
+/// let squares: Vec<_> = handles.into_iter().map(pollster::block_on).collect();
+/// assert_eq!(squares, [1, 4, 9, 16]);
+/// ```
+pub struct ThreadPool {
+    shared: Arc<Shared>,
+}
+
+impl ThreadPool {
+    /// Create a pool with one worker per available CPU.
+    pub fn new() -> io::Result<Self> {
+        Self::builder().create()
+    }
+
+    /// Configure a new pool.
+    pub fn builder() -> ThreadPoolBuilder {
+        ThreadPoolBuilder::new()
+    }
+
+    /// Spawn a future onto the pool, returning a handle that resolves to its output.
+    ///
+    /// If the future panics, what happens depends on the pool's [`PanicPolicy`]; by default the
+    /// panic is resumed when the handle is awaited.
+    #[track_caller]
+    pub fn spawn<F>(&self, fut: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (task, handle) = JoinHandle::wrap(fut);
+        self.spawn_ok(task);
+        handle
+    }
+
+    /// Like [`spawn`](ThreadPool::spawn), but give the task a name, which is passed to the
+    /// [task panic hook](crate::set_task_panic_hook) and printed if the task panics.
+    #[track_caller]
+    pub fn spawn_named<F>(&self, name: &'static str, fut: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (task, handle) = JoinHandle::wrap(fut);
+        spawn_task(&self.shared, Some(name), Box::pin(task), Location::caller());
+        handle
+    }
+
+    /// Spawn a future onto the pool without a way to wait for it.
+    #[track_caller]
+    pub fn spawn_ok<F>(&self, fut: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.spawn_boxed(Box::pin(fut), Location::caller());
+    }
+
+    /// Run `f` with a [`Scope`] for spawning futures that borrow from the caller's stack, blocking
+    /// until all of them have completed.
+    ///
+    /// If `f` or any future spawned on the scope panics, including while being dropped, the scope
+    /// is [cancelled](Scope::cancel) and the panic is resumed once every spawned future has been
+    /// dropped. Calling this from one of the pool's own workers ties up that worker while waiting,
+    /// so a pool with a single worker would deadlock.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use pollster::pool::ThreadPool;
+    ///
+    /// let pool = ThreadPool::new().unwrap();
+    /// let words = vec!["scoped", "futures", "borrow"];
+    /// let letters = AtomicUsize::new(0);
+    /// pool.scope(|s| {
+    ///     for word in &words {
+    ///         let letters = &letters;
+    ///         s.spawn(async move {
+    ///             letters.fetch_add(word.len(), Ordering::Relaxed);
+    ///         });
+    ///     }
+    /// });
+    /// assert_eq!(letters.into_inner(), 19);
+    /// ```
+    pub fn scope<'env, F, T>(&self, f: F) -> T
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> T,
+    {
+        let scope = Scope {
+            pool: self,
+            state: Arc::new(ScopeState {
+                running: Mutex::new(0),
+                cond: Condvar::new(),
+                panic: Mutex::new(None),
+                cancelled: AtomicBool::new(false),
+                next_id: AtomicU64::new(0),
+                wakers: Mutex::new(HashMap::new()),
+            }),
+            scope: PhantomData,
+            env: PhantomData,
+        };
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(&scope)));
+        if result.is_err() {
+            scope.state.cancel();
+        }
+
+        let mut running = scope.state.running.lock().unwrap();
+        while *running > 0 {
+            running = scope.state.cond.wait(running).unwrap();
+        }
+        drop(running);
+
+        let task_panic = scope.state.panic.lock().unwrap().take();
+        match (result, task_panic) {
+            (Err(payload), _) | (Ok(_), Some(payload)) => panic::resume_unwind(payload),
+            (Ok(value), None) => value,
+        }
+    }
+
+    /// Get a thread-safe [`Handle`] for spawning onto the pool, which doesn't keep the pool alive.
+    pub fn handle(&self) -> Handle {
+        Handle::new(Arc::new(Remote(Arc::downgrade(&self.shared))))
+    }
+
+    /// List the pool's live tasks: those that haven't completed, and haven't been dropped along
+    /// with every waker for them.
+    ///
+    /// With the `signal` feature on Unix, [`dump_on_signal`] prints this for every pool when the
+    /// process receives `SIGUSR1`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use pollster::pool::ThreadPool;
+    /// use pollster::TaskState;
+    ///
+    /// let pool = ThreadPool::new().unwrap();
+    /// let (config, abort) = pollster::future::abortable(std::future::pending::<()>());
+    /// let stuck = pool.spawn_named("wait-for-config", config);
+    /// # while pool.dump().iter().any(|task| task.state() != TaskState::Waiting) { std::thread::yield_now(); }
+    /// let tasks = pool.dump();
+    /// assert_eq!(tasks.len(), 1);
+    /// assert_eq!(tasks[0].name(), Some("wait-for-config"));
+    /// assert_eq!(tasks[0].state(), TaskState::Waiting);
+    /// println!("{}", tasks[0]);
+    ///
+    /// abort.abort();
+    /// assert!(pollster::block_on(stuck).is_err());
+    /// ```
+    pub fn dump(&self) -> Vec<TaskInfo> {
+        self.shared.dump()
+    }
+
+    fn spawn_boxed(&self, fut: SendFuture, location: &'static Location<'static>) {
+        spawn_task(&self.shared, None, fut, location);
+    }
+}
+
+fn spawn_task(shared: &Arc<Shared>, name: Option<&'static str>, fut: SendFuture, location: &'static Location<'static>) {
+    let task = Arc::new(Task {
+        id: shared.next_id.fetch_add(1, Ordering::Relaxed),
+        state: AtomicU8::new(SCHEDULED),
+        future: Mutex::new(Some(fut)),
+        name,
+        location,
+        polls: AtomicU64::new(0),
+        pool: Arc::clone(shared),
+    });
+    shared.tasks.lock().unwrap().insert(task.id, Arc::downgrade(&task));
+    shared.push(task);
+    #[cfg(feature = "stats")]
+    crate::stats::record_spawn();
+}
+
+// Spawns for a `Handle`, as long as some `ThreadPool` for the workers is still alive.
+struct Remote(Weak<Shared>);
+
+impl Spawn for Remote {
+    fn spawn(&self, fut: SendFuture, location: &'static Location<'static>) -> Result<(), SpawnError> {
+        match self.0.upgrade() {
+            Some(shared) if shared.queue.lock().unwrap().handles > 0 => {
+                spawn_task(&shared, None, fut, location);
+                Ok(())
+            }
+            _ => Err(SpawnError::new()),
+        }
+    }
+}
+
+// Every pool created, for `dump_on_signal`.
+#[cfg(all(feature = "signal", unix))]
+static POOLS: Mutex<Vec<Weak<Shared>>> = Mutex::new(Vec::new());
+
+#[cfg(all(feature = "signal", unix))]
+fn register(shared: &Arc<Shared>) {
+    let mut pools = POOLS.lock().unwrap();
+    pools.retain(|pool| pool.strong_count() > 0);
+    pools.push(Arc::downgrade(shared));
+}
+
+/// Print the live tasks of every [`ThreadPool`] to stderr whenever the process receives
+/// `SIGUSR1`, as listed by [`ThreadPool::dump`]. Requires the `signal` feature, on Unix.
+///
+/// This starts a helper thread the first time it is called; later calls do nothing. When a
+/// service wedges, `kill -USR1 <pid>` then shows what each of its tasks is waiting on.
+///
+/// # Errors
+///
+/// Fails if the signal handler or the helper thread can't be set up.
+#[cfg(all(feature = "signal", unix))]
+pub fn dump_on_signal() -> io::Result<()> {
+    use crate::signal::{listen, Signal};
+    use futures_core::Stream;
+
+    static STARTED: Mutex<bool> = Mutex::new(false);
+    let mut started = STARTED.lock().unwrap();
+    if *started {
+        return Ok(());
+    }
+    let mut signals = listen([Signal::User1])?;
+    thread::Builder::new().name("pollster-dump".into()).spawn(move || {
+        while crate::block_on(std::future::poll_fn(|cx| Pin::new(&mut signals).poll_next(cx))).is_some() {
+            let pools: Vec<_> = POOLS.lock().unwrap().iter().filter_map(Weak::upgrade).collect();
+            for (index, pool) in pools.iter().enumerate() {
+                let tasks = pool.dump();
+                eprintln!("pollster: thread pool #{}, {} live task(s)", index, tasks.len());
+                for task in tasks {
+                    eprintln!("  {}", task);
+                }
+            }
+        }
+    })?;
+    *started = true;
+    Ok(())
+}
+
+// Like `Handle`'s, the future given to `block_on` is driven on the current thread.
+impl Executor for ThreadPool {
+    #[track_caller]
+    fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        crate::block_on(fut)
+    }
+
+    #[track_caller]
+    fn spawn<F>(&self, fut: F) -> Result<JoinHandle<F::Output>, SpawnError>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        Ok(ThreadPool::spawn(self, fut))
+    }
+}
+
+impl Clone for ThreadPool {
+    fn clone(&self) -> Self {
+        self.shared.queue.lock().unwrap().handles += 1;
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.handles -= 1;
+        if queue.handles == 0 {
+            self.shared.cond.notify_all();
+        }
+    }
+}
+
+impl fmt::Debug for ThreadPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThreadPool").finish_non_exhaustive()
+    }
+}
+
+/// Builder for a [`ThreadPool`], returned by [`ThreadPool::builder`].
+#[derive(Clone)]
+pub struct ThreadPoolBuilder {
+    pool_size: usize,
+    stack_size: Option<usize>,
+    name_prefix: String,
+    on_thread_start: Option<Hook>,
+    on_thread_stop: Option<Hook>,
+    on_task_panic: PanicPolicy,
+}
+
+impl ThreadPoolBuilder {
+    /// Start with one worker per available CPU, the default stack size, and workers named
+    /// `pollster-pool-0`, `pollster-pool-1`, and so on.
+    pub fn new() -> Self {
+        Self {
+            pool_size: thread::available_parallelism().map_or(1, |n| n.get()),
+            stack_size: None,
+            name_prefix: "pollster-pool-".into(),
+            on_thread_start: None,
+            on_thread_stop: None,
+            on_task_panic: PanicPolicy::Propagate,
+        }
+    }
+
+    /// Set the number of worker threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    pub fn pool_size(&mut self, size: usize) -> &mut Self {
+        assert!(size > 0, "thread pool size must be non-zero");
+        self.pool_size = size;
+        self
+    }
+
+    /// Set the stack size of the worker threads, in bytes.
+    pub fn stack_size(&mut self, size: usize) -> &mut Self {
+        self.stack_size = Some(size);
+        self
+    }
+
+    /// Set the prefix of the worker thread names, which are followed by the worker's index.
+    pub fn name_prefix(&mut self, prefix: impl Into<String>) -> &mut Self {
+        self.name_prefix = prefix.into();
+        self
+    }
+
+    /// Run `f` on each worker thread as it starts, before it runs any tasks. It is passed the
+    /// worker's index.
+    ///
+    /// This is the place to register the thread with a profiler, set up thread-local state, or
+    /// pin the thread to a core.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let started = Arc::new(AtomicUsize::new(0));
+    /// let stopped = Arc::new(AtomicUsize::new(0));
+    /// let pool = pollster::pool::ThreadPool::builder()
+    ///     .pool_size(2)
+    ///     .on_thread_start({
+    ///         let started = Arc::clone(&started);
+    ///         move |_| {
+    ///             started.fetch_add(1, Ordering::SeqCst);
+    ///         }
+    ///     })
+    ///     .on_thread_stop({
+    ///         let stopped = Arc::clone(&stopped);
+    ///         move |_| {
+    ///             stopped.fetch_add(1, Ordering::SeqCst);
+    ///         }
+    ///     })
+    ///     .create()
+    ///     .unwrap();
+    /// pollster::block_on(pool.spawn(async {}));
+    /// drop(pool);
+    /// # while stopped.load(Ordering::SeqCst) < 2 { std::thread::yield_now(); }
+    /// assert_eq!(started.load(Ordering::SeqCst), 2);
+    /// ```
+    pub fn on_thread_start(&mut self, f: impl Fn(usize) + Send + Sync + 'static) -> &mut Self {
+        self.on_thread_start = Some(Arc::new(f));
+        self
+    }
+
+    /// Run `f` on each worker thread just before it exits, once the pool has been dropped and its
+    /// queue drained. It is passed the worker's index.
+    pub fn on_thread_stop(&mut self, f: impl Fn(usize) + Send + Sync + 'static) -> &mut Self {
+        self.on_thread_stop = Some(Arc::new(f));
+        self
+    }
+
+    /// Set what happens when a task panics. The default is [`PanicPolicy::Propagate`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use pollster::{pool::ThreadPool, PanicPolicy};
+    ///
+    /// let pool = ThreadPool::builder().on_task_panic(PanicPolicy::LogAndContinue).create().unwrap();
+    /// pool.spawn_ok(async { panic!("logged, and the pool carries on") });
+    /// assert_eq!(pollster::block_on(pool.spawn(async { 1 + 1 })), 2);
+    /// ```
+    pub fn on_task_panic(&mut self, policy: PanicPolicy) -> &mut Self {
+        self.on_task_panic = policy;
+        self
+    }
+
+    /// Start the worker threads.
+    pub fn create(&mut self) -> io::Result<ThreadPool> {
+        let pool = ThreadPool {
+            shared: Arc::new(Shared {
+                queue: Mutex::new(Queue {
+                    tasks: VecDeque::new(),
+                    handles: 1,
+                }),
+                cond: Condvar::new(),
+                policy: self.on_task_panic,
+                tasks: Mutex::new(HashMap::new()),
+                next_id: AtomicU64::new(0),
+            }),
+        };
+        #[cfg(all(feature = "signal", unix))]
+        register(&pool.shared);
+        for index in 0..self.pool_size {
+            let mut builder = thread::Builder::new().name(format!("{}{}", self.name_prefix, index));
+            if let Some(size) = self.stack_size {
+                builder = builder.stack_size(size);
+            }
+            let shared = Arc::clone(&pool.shared);
+            let (on_start, on_stop) = (self.on_thread_start.clone(), self.on_thread_stop.clone());
+            // On failure the workers started so far exit when `pool` is dropped.
+            builder.spawn(move || {
+                if let Some(on_start) = on_start {
+                    on_start(index);
+                }
+                shared.work();
+                if let Some(on_stop) = on_stop {
+                    on_stop(index);
+                }
+            })?;
+        }
+        Ok(pool)
+    }
+}
+
+impl fmt::Debug for ThreadPoolBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThreadPoolBuilder")
+            .field("pool_size", &self.pool_size)
+            .field("stack_size", &self.stack_size)
+            .field("name_prefix", &self.name_prefix)
+            .field("on_task_panic", &self.on_task_panic)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for ThreadPoolBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct ScopeState {
+    running: Mutex<usize>,
+    cond: Condvar,
+    // The first panic of a spawned future.
+    panic: Mutex<Option<Box<dyn Any + Send>>>,
+    cancelled: AtomicBool,
+    next_id: AtomicU64,
+    // Wakers of the spawned futures that have been polled, by id, to wake them when cancelled.
+    wakers: Mutex<HashMap<u64, Waker>>,
+}
+
+impl ScopeState {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    fn cancel(&self) {
+        if !self.cancelled.swap(true, Ordering::AcqRel) {
+            let wakers = mem::take(&mut *self.wakers.lock().unwrap());
+            wakers.into_values().for_each(Waker::wake);
+        }
+    }
+}
+
+/// A scope for spawning futures that borrow non-`'static` data, created by [`ThreadPool::scope`].
+pub struct Scope<'scope, 'env: 'scope> {
+    pool: &'scope ThreadPool,
+    state: Arc<ScopeState>,
+    // Invariant lifetimes, as in `std::thread::Scope`.
+    scope: PhantomData<&'scope mut &'scope ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'scope> Scope<'scope, '_> {
+    /// Spawn a future onto the pool. It may borrow anything that outlives the scope.
+    #[track_caller]
+    pub fn spawn<F>(&'scope self, fut: F)
+    where
+        F: Future<Output = ()> + Send + 'scope,
+    {
+        *self.state.running.lock().unwrap() += 1;
+        let task: Pin<Box<dyn Future<Output = ()> + Send + 'scope>> = Box::pin(ScopedTask {
+            fut: ManuallyDrop::new(fut),
+            id: self.state.next_id.fetch_add(1, Ordering::Relaxed),
+            registered: false,
+            state: Arc::clone(&self.state),
+        });
+        // SAFETY: `ThreadPool::scope` doesn't return before every `ScopedTask` has been dropped,
+        // and a `ScopedTask` drops its future before reporting that, so nothing borrowed by the
+        // future is used after the scope ends.
+        let task: SendFuture = unsafe { mem::transmute(task) };
+        self.pool.spawn_boxed(task, Location::caller());
+    }
+
+    /// Cancel every future spawned on the scope, including ones spawned from now on.
+    ///
+    /// Each future is woken and then dropped on a worker instead of being polled again, so its
+    /// destructors get to clean up. [`ThreadPool::scope`] still waits for all of them to be
+    /// dropped before returning. This can be called from `f`, from a spawned future, or from any
+    /// other thread the scope is shared with, say when a deadline passes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use pollster::pool::ThreadPool;
+    ///
+    /// let pool = ThreadPool::new().unwrap();
+    /// pool.scope(|s| {
+    ///     s.spawn(std::future::pending());
+    ///     s.spawn(async move {
+    ///         pollster::time::sleep(Duration::from_millis(10)).await;
+    ///         s.cancel();
+    ///     });
+    /// });
+    /// ```
+    pub fn cancel(&self) {
+        self.state.cancel();
+    }
+
+    /// Whether the scope has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.is_cancelled()
+    }
+}
+
+impl fmt::Debug for Scope<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scope").finish_non_exhaustive()
+    }
+}
+
+struct ScopedTask<F> {
+    fut: ManuallyDrop<F>,
+    id: u64,
+    // Whether the waker is in `state.wakers`. A task is always woken through the same waker, so
+    // it is stored once.
+    registered: bool,
+    state: Arc<ScopeState>,
+}
+
+impl<F: Future<Output = ()>> Future for ScopedTask<F> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        // SAFETY: `fut` is structurally pinned and dropped in place.
+        let this = unsafe { self.get_unchecked_mut() };
+        if this.state.is_cancelled() {
+            return Poll::Ready(());
+        }
+        if !this.registered {
+            this.registered = true;
+            this.state.wakers.lock().unwrap().insert(this.id, cx.waker().clone());
+            // A cancellation that took the wakers before ours was added would otherwise go
+            // unnoticed.
+            if this.state.is_cancelled() {
+                return Poll::Ready(());
+            }
+        }
+
+        let fut = unsafe { Pin::new_unchecked(&mut *this.fut) };
+        match panic::catch_unwind(AssertUnwindSafe(|| fut.poll(cx))) {
+            Ok(poll) => poll,
+            Err(payload) => {
+                this.state.panic.lock().unwrap().get_or_insert(payload);
+                this.state.cancel();
+                Poll::Ready(())
+            }
+        }
+    }
+}
+
+impl<F> Drop for ScopedTask<F> {
+    fn drop(&mut self) {
+        // A destructor that panics is reported like a panicking poll, rather than unwinding past
+        // the count below and leaving the scope waiting forever.
+        // SAFETY: `fut` is never used again.
+        let fut = &mut self.fut;
+        let dropped = panic::catch_unwind(AssertUnwindSafe(|| unsafe { ManuallyDrop::drop(fut) }));
+        if let Err(payload) = dropped {
+            self.state.panic.lock().unwrap().get_or_insert(payload);
+            self.state.cancel();
+        }
+        if self.registered {
+            self.state.wakers.lock().unwrap().remove(&self.id);
+        }
+        let mut running = self.state.running.lock().unwrap();
+        *running -= 1;
+        if *running == 0 {
+            self.state.cond.notify_all();
+        }
+    }
+}
+
+// What a task sends its `JoinHandle`: its output, or that it panicked, along with the payload if
+// the executor's policy propagates it.
+type Outcome<T> = Result<T, Option<Box<dyn Any + Send>>>;
+
+// Runs a spawned future, sending its outcome to the `JoinHandle`.
+struct CatchUnwind<F: Future> {
+    fut: F,
+    tx: Option<oneshot::Sender<Outcome<F::Output>>>,
+}
+
+impl<F: Future> Future for CatchUnwind<F> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        // SAFETY: `fut` is structurally pinned and `tx` is never pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        let fut = unsafe { Pin::new_unchecked(&mut this.fut) };
+        let result = match panic::catch_unwind(AssertUnwindSafe(|| fut.poll(cx))) {
+            Ok(Poll::Pending) => return Poll::Pending,
+            Ok(Poll::Ready(output)) => Ok(output),
+            Err(payload) => Err(executor::task_panicked(payload)),
+        };
+        if let Some(tx) = this.tx.take() {
+            tx.send(result);
+        }
+        Poll::Ready(())
+    }
+}
+
+/// Future returned by [`ThreadPool::spawn`] and [`Handle::spawn`], resolving to the spawned
+/// future's output.
+///
+/// Dropping the handle does not cancel the task.
+pub struct JoinHandle<T> {
+    rx: oneshot::Receiver<Outcome<T>>,
+}
+
+impl<T> JoinHandle<T> {
+    // Wrap `fut` into a task that sends its output, or its panic, to the returned handle.
+    pub(crate) fn wrap<F>(fut: F) -> (impl Future<Output = ()>, Self)
+    where
+        F: Future<Output = T>,
+    {
+        let (tx, rx) = oneshot::channel();
+        (CatchUnwind { fut, tx: Some(tx) }, JoinHandle { rx })
+    }
+
+    /// Wait for the task without panicking if it didn't complete, failing with a [`JoinError`]
+    /// instead.
+    ///
+    /// Awaiting the handle itself resumes the task's panic, or panics if the executor's
+    /// [`PanicPolicy`] didn't keep one to resume.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use pollster::{pool::ThreadPool, PanicPolicy};
+    ///
+    /// let pool = ThreadPool::builder().on_task_panic(PanicPolicy::LogAndContinue).create().unwrap();
+    /// let failed = pool.spawn(async { panic!("background task failed") });
+    /// let err = pollster::block_on(failed.try_join()).unwrap_err();
+    /// assert!(err.is_panic());
+    /// assert_eq!(pollster::block_on(pool.spawn(async { 42 }).try_join()).unwrap(), 42);
+    /// ```
+    pub fn try_join(self) -> TryJoin<T> {
+        TryJoin { rx: self.rx }
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        Pin::new(&mut self.rx).poll(cx).map(|result| match result {
+            Some(Ok(value)) => value,
+            Some(Err(Some(payload))) => panic::resume_unwind(payload),
+            Some(Err(None)) => panic!("task panicked"),
+            None => panic!("task was dropped before completing"),
+        })
+    }
+}
+
+impl<T> fmt::Debug for JoinHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinHandle").finish_non_exhaustive()
+    }
+}
+
+/// Future returned by [`JoinHandle::try_join`].
+pub struct TryJoin<T> {
+    rx: oneshot::Receiver<Outcome<T>>,
+}
+
+impl<T> Future for TryJoin<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.rx).poll(cx).map(|result| match result {
+            Some(Ok(value)) => Ok(value),
+            Some(Err(payload)) => Err(JoinError { panicked: true, payload }),
+            None => Err(JoinError { panicked: false, payload: None }),
+        })
+    }
+}
+
+impl<T> fmt::Debug for TryJoin<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TryJoin").finish_non_exhaustive()
+    }
+}
+
+/// Error returned by [`JoinHandle::try_join`] when the task didn't complete: it either panicked or
+/// was dropped first, say because its pool was.
+pub struct JoinError {
+    panicked: bool,
+    payload: Option<Box<dyn Any + Send>>,
+}
+
+impl JoinError {
+    /// Whether the task panicked, rather than being dropped.
+    pub fn is_panic(&self) -> bool {
+        self.panicked
+    }
+
+    /// The task's panic payload, if it panicked under [`PanicPolicy::Propagate`]. Other policies
+    /// don't keep it.
+    pub fn into_panic(self) -> Option<Box<dyn Any + Send>> {
+        self.payload
+    }
+}
+
+impl fmt::Debug for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinError")
+            .field("panicked", &self.panicked)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.panicked {
+            "task panicked"
+        } else {
+            "task was dropped before completing"
+        })
+    }
+}
+
+impl std::error::Error for JoinError {}
@@ -0,0 +1,113 @@
+// Per-call instrumentation for `block_on`: profiler scopes, the trace timeline, waker
+// diagnostics and runtime statistics. Without the corresponding features all of it compiles down
+// to nothing.
+
+use crate::{profile, Signal};
+use std::{
+    fmt,
+    panic::Location,
+    sync::Arc,
+    task::{Poll, Waker},
+};
+#[cfg(feature = "trace")]
+use crate::trace::{EventKind, Origin};
+
+/// Where a `block_on` call was made, and the name it was given, if any.
+#[derive(Clone, Copy)]
+pub(crate) struct Site {
+    pub(crate) location: &'static Location<'static>,
+    pub(crate) name: Option<&'static str>,
+}
+
+impl fmt::Display for Site {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name {
+            Some(name) => write!(f, "{} ({})", self.location, name),
+            None => write!(f, "{}", self.location),
+        }
+    }
+}
+
+pub(crate) struct Probe {
+    site: Site,
+    #[cfg(feature = "trace")]
+    origin: Origin,
+}
+
+impl Probe {
+    #[inline]
+    pub(crate) fn new(site: Site) -> Self {
+        #[cfg(feature = "stats")]
+        crate::stats::record_block_on();
+        Self {
+            site,
+            #[cfg(feature = "trace")]
+            origin: Origin::new(site),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn site(&self) -> Site {
+        self.site
+    }
+
+    /// Create the waker for `signal`, instrumented if any feature needs to observe it.
+    #[inline]
+    #[cfg_attr(not(feature = "debug-waker"), allow(clippy::extra_unused_type_parameters))]
+    pub(crate) fn waker<F>(&self, signal: &Arc<Signal>) -> Waker {
+        #[cfg(feature = "debug-waker")]
+        signal.instrument.stats.set_label(crate::type_label::<F>(), self.site);
+        #[cfg(feature = "trace")]
+        let _ = signal.instrument.origin.set(self.origin);
+
+        #[cfg(any(feature = "debug-waker", feature = "trace"))]
+        return crate::instrument::waker(signal);
+        #[cfg(not(any(feature = "debug-waker", feature = "trace")))]
+        return Waker::from(Arc::clone(signal));
+    }
+
+    /// Run a single poll. `signal` is `None` for the initial poll with a no-op waker.
+    #[inline]
+    pub(crate) fn poll<T>(&self, signal: Option<&Signal>, poll: impl FnOnce() -> Poll<T>) -> Poll<T> {
+        let _scope = profile::poll_scope(self.site);
+        #[cfg(feature = "debug-waker")]
+        if let Some(signal) = signal {
+            signal.instrument.stats.before_poll();
+        }
+        #[cfg(not(feature = "debug-waker"))]
+        let _ = signal;
+        #[cfg(feature = "trace")]
+        self.origin.record(EventKind::PollStart);
+
+        let result = poll();
+
+        #[cfg(feature = "trace")]
+        self.origin.record(EventKind::PollEnd { ready: result.is_ready() });
+        #[cfg(feature = "debug-waker")]
+        if let (Some(signal), true) = (signal, result.is_ready()) {
+            signal.instrument.stats.complete();
+        }
+        result
+    }
+
+    /// Run a park of the blocked thread.
+    #[inline]
+    pub(crate) fn park<T>(&self, park: impl FnOnce() -> T) -> T {
+        let _scope = profile::park_scope(self.site);
+        #[cfg(feature = "stats")]
+        let _parked = crate::stats::Parked::new();
+        #[cfg(feature = "trace")]
+        self.origin.record(EventKind::Park);
+        let result = park();
+        #[cfg(feature = "trace")]
+        self.origin.record(EventKind::Unpark);
+        result
+    }
+
+    /// Called right before `block_on` panics because its future can never be woken.
+    #[inline]
+    pub(crate) fn deadlocked(&self) {
+        #[cfg(feature = "trace")]
+        crate::trace::dump();
+    }
+}
@@ -0,0 +1,73 @@
+//! Asynchronous waiting on child processes.
+
+use crate::oneshot;
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    process::{Child, ExitStatus, Output},
+    task::{Context, Poll},
+    thread,
+};
+
+/// Extension methods for [`Child`] that wait for the process to exit without blocking.
+///
+/// Each call moves the child onto a helper reaper thread which performs the blocking wait, so the
+/// returned future can be raced or joined with other futures. If the future is dropped early, the
+/// reaper thread still collects the process once it exits.
+///
+/// # Example
+///
+/// ```
+/// use std::process::Command;
+/// use pollster::process::ChildExt as _;
+///
+/// let child = Command::new("rustc").arg("--version").spawn().unwrap();
+/// let status = pollster::block_on(child.status_async()).unwrap();
+/// assert!(status.success());
+/// ```
+pub trait ChildExt {
+    /// Wait for the child to exit, like [`Child::wait`].
+    fn status_async(self) -> ChildWait<ExitStatus>;
+
+    /// Wait for the child to exit and collect its output, like [`Child::wait_with_output`].
+    fn wait_with_output_async(self) -> ChildWait<Output>;
+}
+
+impl ChildExt for Child {
+    fn status_async(mut self) -> ChildWait<ExitStatus> {
+        reap(move || self.wait())
+    }
+
+    fn wait_with_output_async(self) -> ChildWait<Output> {
+        reap(move || self.wait_with_output())
+    }
+}
+
+fn reap<T, F>(wait: F) -> ChildWait<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+    // If the thread fails to start, `tx` is dropped with the closure and the receiver reports it.
+    let _ = thread::Builder::new()
+        .name("pollster-reaper".into())
+        .spawn(move || tx.send(wait()));
+    ChildWait { rx }
+}
+
+/// Future returned by the methods of [`ChildExt`].
+pub struct ChildWait<T> {
+    rx: oneshot::Receiver<io::Result<T>>,
+}
+
+impl<T> Future for ChildWait<T> {
+    type Output = io::Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.rx).poll(cx).map(|result| {
+            result.unwrap_or_else(|| Err(io::Error::other("child reaper thread failed")))
+        })
+    }
+}
@@ -0,0 +1,43 @@
+// Profiler integration for the `profile-puffin` and `profile-tracy` features. Without either of
+// them enabled everything here compiles down to nothing.
+
+use crate::probe::Site;
+
+pub(crate) struct Scope {
+    #[cfg(feature = "profile-puffin")]
+    _puffin: Option<puffin::ProfilerScope>,
+    #[cfg(feature = "profile-tracy")]
+    _tracy: Option<tracy_client::Span>,
+}
+
+// Puffin registers scope names once per macro call site, so every scope needs its own function.
+macro_rules! scope_fn {
+    ($fn_name:ident, $name:literal) => {
+        #[inline]
+        #[allow(unused_variables)]
+        pub(crate) fn $fn_name(site: Site) -> Scope {
+            Scope {
+                #[cfg(feature = "profile-puffin")]
+                _puffin: puffin::profile_scope_custom!($name, site.to_string()),
+                #[cfg(feature = "profile-tracy")]
+                _tracy: tracy_client::Client::running().map(|client| {
+                    let function = site.name.unwrap_or("pollster::block_on");
+                    client.span_alloc(Some($name), function, site.location.file(), site.location.line(), 0)
+                }),
+            }
+        }
+    };
+}
+
+scope_fn!(poll_scope, "pollster::poll");
+scope_fn!(park_scope, "pollster::park");
+
+#[inline]
+pub(crate) fn wake_marker() {
+    #[cfg(feature = "profile-puffin")]
+    drop(puffin::profile_scope_custom!("pollster::wake"));
+    #[cfg(feature = "profile-tracy")]
+    if let Some(client) = tracy_client::Client::running() {
+        client.message("pollster::wake", 0);
+    }
+}
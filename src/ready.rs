@@ -0,0 +1,124 @@
+// Wake tracking for drivers that poll many futures from a single task. Each future gets a waker of
+// its own that queues the future's index, so a wake only repolls the futures that were actually
+// woken, in the order they were woken.
+
+use std::{
+    collections::VecDeque,
+    mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Wake, Waker},
+};
+
+struct State {
+    ids: VecDeque<usize>,
+    // The driver's own waker, woken when some future becomes ready.
+    waker: Option<Waker>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+}
+
+struct Child {
+    index: usize,
+    queued: AtomicBool,
+    shared: Arc<Shared>,
+}
+
+impl Wake for Child {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        if !self.queued.swap(true, Ordering::AcqRel) {
+            let mut state = self.shared.state.lock().unwrap();
+            state.ids.push_back(self.index);
+            let waker = state.waker.take();
+            drop(state);
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+    }
+}
+
+pub struct ReadyQueue {
+    shared: Arc<Shared>,
+    children: Vec<Arc<Child>>,
+    wakers: Vec<Waker>,
+}
+
+impl ReadyQueue {
+    // A queue of `len` slots, all queued so they are polled first thing.
+    pub fn with_len(len: usize) -> Self {
+        let mut ready = Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    ids: VecDeque::with_capacity(len),
+                    waker: None,
+                }),
+            }),
+            children: Vec::with_capacity(len),
+            wakers: Vec::with_capacity(len),
+        };
+        for _ in 0..len {
+            ready.add();
+        }
+        ready
+    }
+
+    // Add a slot, queued so it is polled next. Returns its index.
+    pub fn add(&mut self) -> usize {
+        let index = self.children.len();
+        let child = Arc::new(Child {
+            index,
+            queued: AtomicBool::new(true),
+            shared: Arc::clone(&self.shared),
+        });
+        self.wakers.push(Waker::from(Arc::clone(&child)));
+        self.children.push(child);
+        self.shared.state.lock().unwrap().ids.push_back(index);
+        index
+    }
+
+    // Queue a slot that was given a new future, without waking the driver, which must be the one
+    // calling this.
+    pub(crate) fn schedule(&self, index: usize) {
+        if !self.children[index].queued.swap(true, Ordering::AcqRel) {
+            self.shared.state.lock().unwrap().ids.push_back(index);
+        }
+    }
+
+    // Take the slots woken so far, earliest wake first, registering `waker` to hear about the
+    // next ones. Wakes from then on queue the slots again.
+    pub fn take(&self, waker: &Waker) -> VecDeque<usize> {
+        let mut state = self.shared.state.lock().unwrap();
+        match &state.waker {
+            Some(current) if current.will_wake(waker) => {}
+            _ => state.waker = Some(waker.clone()),
+        }
+        let ids = mem::take(&mut state.ids);
+        for &index in &ids {
+            self.children[index].queued.store(false, Ordering::Release);
+        }
+        ids
+    }
+
+    // Put back slots that were taken but not polled, ahead of any woken since.
+    #[cfg(feature = "stream")]
+    pub(crate) fn restore(&self, mut ids: VecDeque<usize>) {
+        ids.retain(|&index| !self.children[index].queued.swap(true, Ordering::AcqRel));
+        let mut state = self.shared.state.lock().unwrap();
+        let newer = mem::replace(&mut state.ids, ids);
+        state.ids.extend(newer);
+    }
+
+    // The context to poll slot `index`'s future with.
+    pub fn context(&self, index: usize) -> Context<'_> {
+        Context::from_waker(&self.wakers[index])
+    }
+}
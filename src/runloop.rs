@@ -0,0 +1,115 @@
+use std::{
+    os::raw::{c_int, c_void},
+    ptr,
+};
+
+type Boolean = u8;
+type CFIndex = isize;
+type CFTimeInterval = f64;
+type CFRunLoopRef = *mut c_void;
+type CFRunLoopSourceRef = *mut c_void;
+type CFStringRef = *const c_void;
+
+#[repr(C)]
+struct CFRunLoopSourceContext {
+    version: CFIndex,
+    info: *mut c_void,
+    retain: Option<extern "C" fn(*const c_void) -> *const c_void>,
+    release: Option<extern "C" fn(*const c_void)>,
+    copy_description: Option<extern "C" fn(*const c_void) -> CFStringRef>,
+    equal: Option<extern "C" fn(*const c_void, *const c_void) -> Boolean>,
+    hash: Option<extern "C" fn(*const c_void) -> usize>,
+    schedule: Option<extern "C" fn(*mut c_void, CFRunLoopRef, CFStringRef)>,
+    cancel: Option<extern "C" fn(*mut c_void, CFRunLoopRef, CFStringRef)>,
+    perform: Option<extern "C" fn(*mut c_void)>,
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    static kCFRunLoopDefaultMode: CFStringRef;
+
+    fn CFRunLoopGetMain() -> CFRunLoopRef;
+    fn CFRunLoopWakeUp(rl: CFRunLoopRef);
+    fn CFRunLoopRunInMode(mode: CFStringRef, seconds: CFTimeInterval, return_after_source_handled: Boolean) -> i32;
+    fn CFRunLoopAddSource(rl: CFRunLoopRef, source: CFRunLoopSourceRef, mode: CFStringRef);
+    fn CFRunLoopSourceCreate(
+        allocator: *const c_void,
+        order: CFIndex,
+        context: *mut CFRunLoopSourceContext,
+    ) -> CFRunLoopSourceRef;
+    fn CFRunLoopSourceSignal(source: CFRunLoopSourceRef);
+    fn CFRunLoopSourceInvalidate(source: CFRunLoopSourceRef);
+    fn CFRelease(cf: *const c_void);
+}
+
+extern "C" {
+    fn pthread_main_np() -> c_int;
+}
+
+extern "C" fn perform(_info: *mut c_void) {}
+
+/// A run loop source attached to the main run loop, used to interrupt `MainRunLoop::run` from any
+/// thread.
+pub(crate) struct MainRunLoop {
+    run_loop: CFRunLoopRef,
+    source: CFRunLoopSourceRef,
+}
+
+// Signalling a run loop source and waking a run loop are both documented as thread-safe.
+unsafe impl Send for MainRunLoop {}
+unsafe impl Sync for MainRunLoop {}
+
+impl MainRunLoop {
+    /// Returns `None` unless called on the main thread.
+    pub(crate) fn current() -> Option<Self> {
+        if unsafe { pthread_main_np() } == 0 {
+            return None;
+        }
+
+        let mut context = CFRunLoopSourceContext {
+            version: 0,
+            info: ptr::null_mut(),
+            retain: None,
+            release: None,
+            copy_description: None,
+            equal: None,
+            hash: None,
+            schedule: None,
+            cancel: None,
+            perform: Some(perform),
+        };
+        unsafe {
+            let run_loop = CFRunLoopGetMain();
+            let source = CFRunLoopSourceCreate(ptr::null(), 0, &mut context);
+            if source.is_null() {
+                return None;
+            }
+            CFRunLoopAddSource(run_loop, source, kCFRunLoopDefaultMode);
+            Some(Self { run_loop, source })
+        }
+    }
+
+    /// Run the main run loop until at least one source (possibly our own) has been handled.
+    pub(crate) fn run(&self) {
+        unsafe {
+            CFRunLoopRunInMode(kCFRunLoopDefaultMode, CFTimeInterval::MAX, 1);
+        }
+    }
+
+    /// Make a concurrent or future call to `run` return.
+    pub(crate) fn wake(&self) {
+        unsafe {
+            CFRunLoopSourceSignal(self.source);
+            CFRunLoopWakeUp(self.run_loop);
+        }
+    }
+}
+
+impl Drop for MainRunLoop {
+    fn drop(&mut self) {
+        unsafe {
+            CFRunLoopSourceInvalidate(self.source);
+            CFRelease(self.source);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+/// Stop the helper threads that the crate starts on demand, and wait for them to exit.
+///
+/// This covers the timer thread behind [`time`](crate::time) and the threads of the
+/// [`unblock`](crate::unblock) pool. Pending sleeps complete straight away, reporting it through
+/// [`Sleep::is_shut_down`](crate::time::Sleep::is_shut_down), and timeouts waiting on them fail
+/// with an [`Elapsed`](crate::time::Elapsed) that does the same. Jobs already queued on the blocking pool still run first, and
+/// this waits for them to finish. The threads behind signal handling and the `io` module's stdin
+/// and stdout spend their lives blocked in system calls, so they are not stopped.
+///
+/// Using the timers or the pool afterwards starts fresh threads, so a library that is loaded and
+/// unloaded repeatedly can call this before each unload.
+///
+/// When the process exits, or the library containing this crate is unloaded, the threads are
+/// asked to stop automatically, but they aren't waited for, since joining threads in an exit
+/// handler can deadlock.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// pollster::block_on(pollster::time::sleep(Duration::from_millis(1)));
+/// assert_eq!(pollster::block_on(pollster::unblock(|| 1 + 1)), 2);
+/// pollster::shutdown();
+///
+/// // Everything starts up again on demand.
+/// pollster::block_on(pollster::time::sleep(Duration::from_millis(1)));
+/// ```
+pub fn shutdown() {
+    stop(true);
+}
+
+fn stop(wait: bool) {
+    crate::time::shutdown(wait);
+    crate::unblock::shutdown(wait);
+}
+
+// Ask the helper threads to stop when the process exits, or when the library is unloaded.
+pub(crate) fn stop_at_exit() {
+    #[cfg(any(unix, windows))]
+    {
+        static REGISTER: std::sync::Once = std::sync::Once::new();
+
+        extern "C" {
+            fn atexit(callback: extern "C" fn()) -> std::os::raw::c_int;
+        }
+
+        extern "C" fn at_exit() {
+            // Unwinding out of an exit handler would abort.
+            let _ = std::panic::catch_unwind(|| stop(false));
+        }
+
+        // SAFETY: `at_exit` is a plain function that stays valid for as long as this code is
+        // loaded.
+        REGISTER.call_once(|| unsafe {
+            atexit(at_exit);
+        });
+    }
+}
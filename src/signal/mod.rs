@@ -0,0 +1,157 @@
+//! Receiving OS signals as a [`Stream`].
+//!
+//! On Unix these are real signals. On Windows, console control events are mapped onto the closest
+//! signal: Ctrl-C to [`Signal::Int`], Ctrl-Break to [`Signal::Quit`], closing the console window to
+//! [`Signal::Hup`], and logoff or shutdown to [`Signal::Term`].
+
+#[cfg(unix)]
+mod unix;
+#[cfg(windows)]
+mod windows;
+
+#[cfg(unix)]
+use unix as sys;
+#[cfg(windows)]
+use windows as sys;
+
+use futures_core::Stream;
+use std::{
+    collections::VecDeque,
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex, Weak},
+    task::{Context, Poll, Waker},
+};
+
+/// A signal that can be listened for with [`listen`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Signal {
+    /// `SIGINT`, or Ctrl-C on Windows.
+    Int,
+    /// `SIGTERM`, or logoff and shutdown on Windows.
+    Term,
+    /// `SIGHUP`, or the console window being closed on Windows.
+    Hup,
+    /// `SIGQUIT`, or Ctrl-Break on Windows.
+    Quit,
+    /// `SIGUSR1`. Unix only.
+    #[cfg(unix)]
+    User1,
+}
+
+impl Signal {
+    const ALL: &'static [Signal] = &[
+        Signal::Int,
+        Signal::Term,
+        Signal::Hup,
+        Signal::Quit,
+        #[cfg(unix)]
+        Signal::User1,
+    ];
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+struct Listener {
+    mask: u8,
+    state: Mutex<ListenerState>,
+}
+
+struct ListenerState {
+    pending: VecDeque<Signal>,
+    waker: Option<Waker>,
+}
+
+static LISTENERS: Mutex<Vec<Weak<Listener>>> = Mutex::new(Vec::new());
+
+/// Hand `signal` to every listener waiting for it, returning whether there were any.
+fn dispatch(signal: Signal) -> bool {
+    let mut matched = false;
+    let mut wakers = Vec::new();
+    let mut listeners = LISTENERS.lock().unwrap();
+    listeners.retain(|listener| listener.strong_count() > 0);
+    for listener in listeners.iter().filter_map(Weak::upgrade) {
+        if listener.mask & signal.bit() == 0 {
+            continue;
+        }
+        matched = true;
+        let mut state = listener.state.lock().unwrap();
+        // Repeats of a signal that hasn't been received yet are coalesced.
+        if !state.pending.contains(&signal) {
+            state.pending.push_back(signal);
+        }
+        wakers.extend(state.waker.take());
+    }
+    drop(listeners);
+    wakers.into_iter().for_each(Waker::wake);
+    matched
+}
+
+/// Start listening for the given signals, returning a stream that yields each one as it arrives.
+///
+/// Once a signal has been listened for, it no longer triggers its default action (usually
+/// terminating the process) for as long as the process runs, even after the stream is dropped.
+/// A signal that arrives again before the stream has yielded it is only yielded once.
+///
+/// # Errors
+///
+/// Fails if the signal handlers or the helper thread that forwards signals can't be set up.
+///
+/// # Example
+///
+/// Race a daemon's main future against shutdown signals:
+///
+/// ```no_run
+/// use pollster::signal::{listen, Signal};
+///
+/// async fn serve() { /* ... */ }
+///
+/// let mut signals = listen([Signal::Term, Signal::Int]).unwrap();
+/// let next_signal = std::future::poll_fn(|cx| {
+///     futures_core::Stream::poll_next(std::pin::Pin::new(&mut signals), cx)
+/// });
+/// pollster::select! {
+///     () = serve() => println!("done"),
+///     signal = next_signal => println!("shutting down on {:?}", signal),
+/// }
+/// ```
+pub fn listen(signals: impl IntoIterator<Item = Signal>) -> io::Result<Signals> {
+    let mask = signals.into_iter().fold(0, |mask, signal| mask | signal.bit());
+    for signal in Signal::ALL.iter().copied().filter(|signal| mask & signal.bit() != 0) {
+        sys::install(signal)?;
+    }
+    let listener = Arc::new(Listener {
+        mask,
+        state: Mutex::new(ListenerState {
+            pending: VecDeque::new(),
+            waker: None,
+        }),
+    });
+    LISTENERS.lock().unwrap().push(Arc::downgrade(&listener));
+    Ok(Signals { listener })
+}
+
+/// Stream returned by [`listen`]. It never ends.
+pub struct Signals {
+    listener: Arc<Listener>,
+}
+
+impl Stream for Signals {
+    type Item = Signal;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Signal>> {
+        let mut state = self.listener.state.lock().unwrap();
+        match state.pending.pop_front() {
+            Some(signal) => Poll::Ready(Some(signal)),
+            None => {
+                match &mut state.waker {
+                    Some(waker) if waker.will_wake(cx.waker()) => {}
+                    waker => *waker = Some(cx.waker().clone()),
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use super::Signal;
+use libc::{c_int, c_void, SIGHUP, SIGINT, SIGQUIT, SIGTERM, SIGUSR1};
+use std::{
+    io, mem, ptr,
+    sync::{
+        atomic::{AtomicI32, AtomicU8, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+// Where the calling thread's `errno` lives, under the name each libc gives it. On other targets
+// the handler leaves `errno` as its write left it.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "emscripten",
+    target_os = "fuchsia",
+    target_os = "redox",
+    target_os = "hurd",
+    target_os = "dragonfly"
+))]
+use libc::__errno_location as errno_location;
+#[cfg(any(target_vendor = "apple", target_os = "freebsd"))]
+use libc::__error as errno_location;
+#[cfg(any(
+    target_os = "android",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "cygwin"
+))]
+use libc::__errno as errno_location;
+#[cfg(any(target_os = "solaris", target_os = "illumos"))]
+use libc::___errno as errno_location;
+#[cfg(target_os = "haiku")]
+use libc::_errnop as errno_location;
+#[cfg(target_os = "aix")]
+use libc::_Errno as errno_location;
+
+// Signals caught but not yet forwarded, and the pipe used to wake the forwarding thread. The
+// handler only touches these, since little else is async-signal-safe.
+static PENDING: AtomicU8 = AtomicU8::new(0);
+static WAKE_FD: AtomicI32 = AtomicI32::new(-1);
+
+// Bits of the signals whose handler has been installed.
+static INSTALLED: Mutex<u8> = Mutex::new(0);
+
+fn raw(signal: Signal) -> c_int {
+    match signal {
+        Signal::Int => SIGINT,
+        Signal::Term => SIGTERM,
+        Signal::Hup => SIGHUP,
+        Signal::Quit => SIGQUIT,
+        Signal::User1 => SIGUSR1,
+    }
+}
+
+extern "C" fn handle(signum: c_int) {
+    let signal = match signum {
+        SIGINT => Signal::Int,
+        SIGTERM => Signal::Term,
+        SIGHUP => Signal::Hup,
+        SIGQUIT => Signal::Quit,
+        SIGUSR1 => Signal::User1,
+        _ => return,
+    };
+    // Only write when the bit is newly set, so the pipe can never fill up and block the handler.
+    if PENDING.fetch_or(signal.bit(), Ordering::SeqCst) & signal.bit() == 0 {
+        let byte = 0u8;
+        // A failed write would clobber the `errno` of whatever the signal interrupted.
+        let errno = io::Error::last_os_error().raw_os_error();
+        let fd = WAKE_FD.load(Ordering::SeqCst);
+        unsafe { libc::write(fd, (&byte as *const u8).cast::<c_void>(), 1) };
+        restore_errno(errno);
+    }
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "emscripten",
+    target_os = "fuchsia",
+    target_os = "redox",
+    target_os = "hurd",
+    target_os = "dragonfly",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "android",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "cygwin",
+    target_os = "solaris",
+    target_os = "illumos",
+    target_os = "haiku",
+    target_os = "aix"
+))]
+// Put back the `errno` read with `io::Error::last_os_error`.
+fn restore_errno(errno: Option<c_int>) {
+    if let Some(errno) = errno {
+        unsafe { *errno_location() = errno };
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "emscripten",
+    target_os = "fuchsia",
+    target_os = "redox",
+    target_os = "hurd",
+    target_os = "dragonfly",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "android",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "cygwin",
+    target_os = "solaris",
+    target_os = "illumos",
+    target_os = "haiku",
+    target_os = "aix"
+)))]
+fn restore_errno(_: Option<c_int>) {}
+
+fn forward(read_fd: c_int) {
+    let mut byte = 0u8;
+    loop {
+        if unsafe { libc::read(read_fd, (&mut byte as *mut u8).cast::<c_void>(), 1) } < 0 {
+            if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return;
+        }
+        let pending = PENDING.swap(0, Ordering::SeqCst);
+        for signal in Signal::ALL.iter().copied().filter(|signal| pending & signal.bit() != 0) {
+            super::dispatch(signal);
+        }
+    }
+}
+
+// Create the wake pipe, keeping both ends out of child processes.
+fn wake_pipe() -> io::Result<[c_int; 2]> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    for &fd in &fds {
+        if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } != 0 {
+            let err = io::Error::last_os_error();
+            unsafe { libc::close(fds[0]) };
+            unsafe { libc::close(fds[1]) };
+            return Err(err);
+        }
+    }
+    Ok(fds)
+}
+
+pub(super) fn install(signal: Signal) -> io::Result<()> {
+    let mut installed = INSTALLED.lock().unwrap();
+    if *installed & signal.bit() != 0 {
+        return Ok(());
+    }
+    if WAKE_FD.load(Ordering::SeqCst) < 0 {
+        let fds = wake_pipe()?;
+        thread::Builder::new()
+            .name("pollster-signal".into())
+            .spawn(move || forward(fds[0]))?;
+        WAKE_FD.store(fds[1], Ordering::SeqCst);
+    }
+
+    // `sigaction` rather than `signal`, which resets the handler after the first delivery on some
+    // systems, like illumos. `SA_RESTART` keeps the signal from failing system calls elsewhere
+    // with `EINTR`.
+    let mut action: libc::sigaction = unsafe { mem::zeroed() };
+    action.sa_sigaction = handle as extern "C" fn(c_int) as libc::sighandler_t;
+    action.sa_flags = libc::SA_RESTART;
+    unsafe { libc::sigemptyset(&mut action.sa_mask) };
+    if unsafe { libc::sigaction(raw(signal), &action, ptr::null_mut()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    *installed |= signal.bit();
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use super::Signal;
+use std::{io, sync::Mutex};
+
+type Bool = i32;
+
+const CTRL_C_EVENT: u32 = 0;
+const CTRL_BREAK_EVENT: u32 = 1;
+const CTRL_CLOSE_EVENT: u32 = 2;
+const CTRL_LOGOFF_EVENT: u32 = 5;
+const CTRL_SHUTDOWN_EVENT: u32 = 6;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn SetConsoleCtrlHandler(handler: Option<unsafe extern "system" fn(u32) -> Bool>, add: Bool) -> Bool;
+}
+
+static INSTALLED: Mutex<bool> = Mutex::new(false);
+
+// Console control handlers run on a fresh thread, so this can dispatch directly. Events nobody
+// listens for fall through to the next handler, which is the default behaviour.
+unsafe extern "system" fn handle(ctrl_type: u32) -> Bool {
+    let signal = match ctrl_type {
+        CTRL_C_EVENT => Signal::Int,
+        CTRL_BREAK_EVENT => Signal::Quit,
+        CTRL_CLOSE_EVENT => Signal::Hup,
+        CTRL_LOGOFF_EVENT | CTRL_SHUTDOWN_EVENT => Signal::Term,
+        _ => return 0,
+    };
+    super::dispatch(signal) as Bool
+}
+
+pub(super) fn install(_signal: Signal) -> io::Result<()> {
+    let mut installed = INSTALLED.lock().unwrap();
+    if !*installed {
+        if unsafe { SetConsoleCtrlHandler(Some(handle), 1) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        *installed = true;
+    }
+    Ok(())
+}
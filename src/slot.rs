@@ -0,0 +1,61 @@
+use std::{future::Future, marker::PhantomPinned, pin::Pin};
+
+/// Pinned storage for driving fresh instances of a large future one after another.
+///
+/// Passing a future to [`block_on`](crate::block_on) moves it by value, which for big async state
+/// machines means copying kilobytes for every call. A slot is pinned once, for example in a `Box`,
+/// and each [`Slot::block_on`] writes the new future straight into it and drives it there.
+///
+/// # Example
+///
+/// ```
+/// use pollster::Slot;
+///
+/// async fn render(frame: u32) -> u32 {
+///     let scratch = [0u8; 4096];
+///     std::future::ready(()).await;
+///     frame + scratch[0] as u32
+/// }
+///
+/// let mut slot = Box::pin(Slot::new());
+/// for frame in 0..3 {
+///     assert_eq!(slot.as_mut().block_on(|| render(frame)), frame);
+/// }
+/// ```
+pub struct Slot<F> {
+    fut: Option<F>,
+    _pin: PhantomPinned,
+}
+
+impl<F> Slot<F> {
+    /// Create an empty slot.
+    pub const fn new() -> Self {
+        Self {
+            fut: None,
+            _pin: PhantomPinned,
+        }
+    }
+}
+
+impl<F: Future> Slot<F> {
+    /// Store the future returned by `make` in the slot and block the thread until it is ready.
+    ///
+    /// The future is dropped in place once it completes. If it panics instead, it is dropped by the
+    /// next call or when the slot is dropped.
+    #[track_caller]
+    pub fn block_on(self: Pin<&mut Self>, make: impl FnOnce() -> F) -> F::Output {
+        // SAFETY: the future is only ever dropped in place, never moved out of the slot.
+        let this = unsafe { self.get_unchecked_mut() };
+        this.fut = None;
+        let fut = unsafe { Pin::new_unchecked(this.fut.get_or_insert_with(make)) };
+        let output = crate::block_on(fut);
+        this.fut = None;
+        output
+    }
+}
+
+impl<F> Default for Slot<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,97 @@
+// Process-wide counters for the `stats` feature. Every counter is updated with relaxed atomics, so
+// a snapshot is cheap but not necessarily consistent across fields.
+
+use std::{
+    convert::TryFrom,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+static BLOCK_ON_CALLS: AtomicU64 = AtomicU64::new(0);
+static PARKED_THREADS: AtomicUsize = AtomicUsize::new(0);
+static PARK_NANOS: AtomicU64 = AtomicU64::new(0);
+static SPAWNED_TASKS: AtomicU64 = AtomicU64::new(0);
+static WAKER_SLOTS: AtomicUsize = AtomicUsize::new(0);
+
+/// A snapshot of the process-wide counters returned by [`stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Stats {
+    /// Total number of [`block_on`](crate::block_on) calls, including ones that completed without
+    /// parking.
+    pub block_on_calls: u64,
+    /// Number of threads currently parked inside `block_on`.
+    pub parked_threads: usize,
+    /// Total time threads have spent parked inside `block_on`.
+    pub park_time: Duration,
+    /// Total number of spawned tasks: futures spawned onto a
+    /// [`ThreadPool`](crate::pool::ThreadPool), one of its scopes or a
+    /// [`LocalPool`](crate::local::LocalPool), and closures handed to [`unblock`](crate::unblock).
+    pub spawned_tasks: u64,
+    /// Number of task wakers held by live [`LocalPool`](crate::local::LocalPool)s, in use or kept
+    /// for later tasks to reuse. This tracks the most tasks each pool has had alive at once,
+    /// rather than how many it has run.
+    pub waker_slots: usize,
+}
+
+/// Read the process-wide runtime counters.
+///
+/// Requires the `stats` feature. The counters are updated with relaxed atomics, so fields read
+/// while other threads are blocking may be slightly out of step with each other.
+///
+/// # Example
+///
+/// ```
+/// let before = pollster::stats();
+/// pollster::block_on(async {});
+/// assert!(pollster::stats().block_on_calls > before.block_on_calls);
+///
+/// let pool = pollster::local::LocalPool::new();
+/// pool.spawner().spawn_local(async {}).unwrap();
+/// assert!(pollster::stats().spawned_tasks > before.spawned_tasks);
+/// ```
+pub fn stats() -> Stats {
+    Stats {
+        block_on_calls: BLOCK_ON_CALLS.load(Ordering::Relaxed),
+        parked_threads: PARKED_THREADS.load(Ordering::Relaxed),
+        park_time: Duration::from_nanos(PARK_NANOS.load(Ordering::Relaxed)),
+        spawned_tasks: SPAWNED_TASKS.load(Ordering::Relaxed),
+        waker_slots: WAKER_SLOTS.load(Ordering::Relaxed),
+    }
+}
+
+pub(crate) fn record_block_on() {
+    BLOCK_ON_CALLS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn record_spawn() {
+    SPAWNED_TASKS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn add_waker_slots(count: usize) {
+    WAKER_SLOTS.fetch_add(count, Ordering::Relaxed);
+}
+
+pub(crate) fn remove_waker_slots(count: usize) {
+    WAKER_SLOTS.fetch_sub(count, Ordering::Relaxed);
+}
+
+/// Counts the current thread as parked until dropped.
+pub(crate) struct Parked {
+    start: Instant,
+}
+
+impl Parked {
+    pub(crate) fn new() -> Self {
+        PARKED_THREADS.fetch_add(1, Ordering::Relaxed);
+        Self { start: Instant::now() }
+    }
+}
+
+impl Drop for Parked {
+    fn drop(&mut self) {
+        let nanos = u64::try_from(self.start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        PARK_NANOS.fetch_add(nanos, Ordering::Relaxed);
+        PARKED_THREADS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,206 @@
+use crate::ready::ReadyQueue;
+use futures_core::Stream;
+use std::{
+    collections::VecDeque,
+    future::{poll_fn, Future},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+type Inner<S> = Pin<Box<<S as Stream>::Item>>;
+type Output<S> = <<S as Stream>::Item as Future>::Output;
+
+enum Slot<F: Future> {
+    Running(Pin<Box<F>>),
+    Done(F::Output),
+}
+
+// A fixed number of slots for running futures, each with its own waker, so only woken futures are
+// polled again.
+struct Slots<T> {
+    slots: Box<[Option<T>]>,
+    free: Vec<usize>,
+    ready: ReadyQueue,
+}
+
+impl<T> Slots<T> {
+    fn new(limit: usize) -> Self {
+        Self {
+            slots: (0..limit).map(|_| None).collect(),
+            free: (0..limit).rev().collect(),
+            ready: ReadyQueue::with_len(limit),
+        }
+    }
+
+    fn in_flight(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    fn insert(&mut self, value: T) -> usize {
+        let index = self.free.pop().expect("no free slot");
+        self.slots[index] = Some(value);
+        self.ready.schedule(index);
+        index
+    }
+
+    fn remove(&mut self, index: usize) -> Option<T> {
+        let value = self.slots[index].take();
+        if value.is_some() {
+            self.free.push(index);
+        }
+        value
+    }
+}
+
+// Pull futures out of `stream` until `in_flight` of them are running under `limit`.
+fn fill<S: Stream + Unpin>(
+    stream: &mut Option<S>,
+    in_flight: usize,
+    limit: usize,
+    cx: &mut Context<'_>,
+    mut push: impl FnMut(S::Item),
+) {
+    let mut in_flight = in_flight;
+    while in_flight < limit {
+        let item = match stream {
+            Some(stream) => Pin::new(stream).poll_next(cx),
+            None => return,
+        };
+        match item {
+            Poll::Ready(Some(fut)) => {
+                push(fut);
+                in_flight += 1;
+            }
+            Poll::Ready(None) => *stream = None,
+            Poll::Pending => return,
+        }
+    }
+}
+
+/// Iterator returned by [`StreamExt::buffered_blocking`](super::StreamExt::buffered_blocking).
+pub struct BufferedBlocking<S>
+where
+    S: Stream,
+    S::Item: Future,
+{
+    // `None` once the stream has ended.
+    stream: Option<S>,
+    limit: usize,
+    slots: Slots<Slot<S::Item>>,
+    // Indices into `slots`, in the order the futures came out of the stream.
+    order: VecDeque<usize>,
+}
+
+impl<S> BufferedBlocking<S>
+where
+    S: Stream,
+    S::Item: Future,
+{
+    pub(super) fn new(stream: S, limit: usize) -> Self {
+        assert!(limit > 0, "buffered_blocking limit must be non-zero");
+        Self {
+            stream: Some(stream),
+            limit,
+            slots: Slots::new(limit),
+            order: VecDeque::with_capacity(limit),
+        }
+    }
+}
+
+impl<S> Iterator for BufferedBlocking<S>
+where
+    S: Stream + Unpin,
+    S::Item: Future,
+{
+    type Item = Output<S>;
+
+    fn next(&mut self) -> Option<Output<S>> {
+        crate::block_on(poll_fn(|cx| {
+            let (slots, order) = (&mut self.slots, &mut self.order);
+            fill(&mut self.stream, slots.in_flight(), self.limit, cx, |fut| {
+                order.push_back(slots.insert(Slot::Running(Box::pin(fut))))
+            });
+            for index in slots.ready.take(cx.waker()) {
+                if let Some(Slot::Running(fut)) = &mut slots.slots[index] {
+                    if let Poll::Ready(output) = fut.as_mut().poll(&mut slots.ready.context(index)) {
+                        slots.slots[index] = Some(Slot::Done(output));
+                    }
+                }
+            }
+            match order.front() {
+                Some(&index) if matches!(slots.slots[index], Some(Slot::Done(_))) => {
+                    order.pop_front();
+                    match slots.remove(index) {
+                        Some(Slot::Done(output)) => Poll::Ready(Some(output)),
+                        _ => unreachable!(),
+                    }
+                }
+                None if self.stream.is_none() => Poll::Ready(None),
+                _ => Poll::Pending,
+            }
+        }))
+    }
+}
+
+/// Iterator returned by
+/// [`StreamExt::buffer_unordered_blocking`](super::StreamExt::buffer_unordered_blocking).
+pub struct BufferUnorderedBlocking<S>
+where
+    S: Stream,
+    S::Item: Future,
+{
+    // `None` once the stream has ended.
+    stream: Option<S>,
+    limit: usize,
+    running: Slots<Inner<S>>,
+}
+
+impl<S> BufferUnorderedBlocking<S>
+where
+    S: Stream,
+    S::Item: Future,
+{
+    pub(super) fn new(stream: S, limit: usize) -> Self {
+        assert!(limit > 0, "buffer_unordered_blocking limit must be non-zero");
+        Self {
+            stream: Some(stream),
+            limit,
+            running: Slots::new(limit),
+        }
+    }
+}
+
+impl<S> Iterator for BufferUnorderedBlocking<S>
+where
+    S: Stream + Unpin,
+    S::Item: Future,
+{
+    type Item = Output<S>;
+
+    fn next(&mut self) -> Option<Output<S>> {
+        crate::block_on(poll_fn(|cx| {
+            let running = &mut self.running;
+            fill(&mut self.stream, running.in_flight(), self.limit, cx, |fut| {
+                running.insert(Box::pin(fut));
+            });
+            let mut woken = running.ready.take(cx.waker());
+            while let Some(index) = woken.pop_front() {
+                let output = match &mut running.slots[index] {
+                    Some(fut) => fut.as_mut().poll(&mut running.ready.context(index)),
+                    None => continue,
+                };
+                if let Poll::Ready(output) = output {
+                    drop(running.remove(index));
+                    // The rest are polled next time, ahead of any woken since.
+                    running.ready.restore(woken);
+                    return Poll::Ready(Some(output));
+                }
+            }
+            if running.in_flight() == 0 && self.stream.is_none() {
+                Poll::Ready(None)
+            } else {
+                Poll::Pending
+            }
+        }))
+    }
+}
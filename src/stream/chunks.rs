@@ -0,0 +1,84 @@
+use crate::time::{self, Sleep};
+use futures_core::Stream;
+use std::{
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+/// Stream returned by [`StreamExt::chunks_timeout`](super::StreamExt::chunks_timeout).
+#[derive(Debug)]
+pub struct ChunksTimeout<S: Stream> {
+    stream: S,
+    max_items: usize,
+    max_wait: Duration,
+    items: Vec<S::Item>,
+    // Started when the first item of a chunk arrives.
+    deadline: Option<Sleep>,
+    done: bool,
+}
+
+impl<S: Stream> ChunksTimeout<S> {
+    pub(super) fn new(stream: S, max_items: usize, max_wait: Duration) -> Self {
+        assert!(max_items > 0, "chunk size must be non-zero");
+        Self {
+            stream,
+            max_items,
+            max_wait,
+            items: Vec::with_capacity(max_items),
+            deadline: None,
+            done: false,
+        }
+    }
+
+    /// Unwrap the stream, discarding any items collected for the current chunk.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    fn flush(&mut self) -> Vec<S::Item> {
+        self.deadline = None;
+        mem::replace(&mut self.items, Vec::with_capacity(self.max_items))
+    }
+}
+
+impl<S: Stream> Stream for ChunksTimeout<S> {
+    type Item = Vec<S::Item>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Vec<S::Item>>> {
+        // SAFETY: `stream` is never moved out of `self`; the other fields are not structurally
+        // pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        while !this.done {
+            // SAFETY: `ChunksTimeout` has no `Drop` impl and is only `Unpin` when the `stream` it
+            // wraps is, so `stream` stays pinned for as long as `self` is.
+            match unsafe { Pin::new_unchecked(&mut this.stream) }.poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    if this.items.is_empty() {
+                        this.deadline = Some(time::sleep(this.max_wait));
+                    }
+                    this.items.push(item);
+                    if this.items.len() >= this.max_items {
+                        return Poll::Ready(Some(this.flush()));
+                    }
+                }
+                Poll::Ready(None) => this.done = true,
+                Poll::Pending => break,
+            }
+        }
+
+        if this.done {
+            if this.items.is_empty() {
+                Poll::Ready(None)
+            } else {
+                Poll::Ready(Some(this.flush()))
+            }
+        } else if this.deadline.as_mut().is_some_and(|sleep| Pin::new(sleep).poll(cx).is_ready()) {
+            Poll::Ready(Some(this.flush()))
+        } else {
+            Poll::Pending
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::time::{self, Sleep};
+use futures_core::Stream;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+// How many items one poll takes from the stream before yielding, so a stream that's always ready
+// can't keep the task polling forever.
+const BUDGET: usize = 32;
+
+/// Stream returned by [`StreamExt::debounce`](super::StreamExt::debounce).
+#[derive(Debug)]
+pub struct Debounce<S: Stream> {
+    stream: S,
+    quiet_period: Duration,
+    // The latest item, waiting for `sleep` to finish without being replaced.
+    pending: Option<S::Item>,
+    sleep: Sleep,
+    done: bool,
+}
+
+impl<S: Stream> Debounce<S> {
+    pub(super) fn new(stream: S, quiet_period: Duration) -> Self {
+        Self {
+            stream,
+            quiet_period,
+            pending: None,
+            sleep: time::sleep(quiet_period),
+            done: false,
+        }
+    }
+
+    /// Unwrap the stream, discarding any item still waiting to be yielded.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: Stream> Stream for Debounce<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        // SAFETY: `stream` is never moved out of `self`; the other fields are not structurally
+        // pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        let mut budget = BUDGET;
+        while !this.done {
+            if budget == 0 {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            budget -= 1;
+            // SAFETY: `Debounce` has no `Drop` impl and is only `Unpin` when the `stream` it wraps
+            // is, so `stream` stays pinned for as long as `self` is.
+            match unsafe { Pin::new_unchecked(&mut this.stream) }.poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    this.pending = Some(item);
+                    this.sleep.reset(time::deadline_after(this.quiet_period));
+                }
+                Poll::Ready(None) => this.done = true,
+                Poll::Pending => break,
+            }
+        }
+
+        if this.done {
+            // Nothing can replace the last item any more, so there's no reason to hold it back.
+            Poll::Ready(this.pending.take())
+        } else if this.pending.is_some() && Pin::new(&mut this.sleep).poll(cx).is_ready() {
+            Poll::Ready(this.pending.take())
+        } else {
+            Poll::Pending
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::pool::{JoinHandle, ThreadPool};
+use futures_core::Stream;
+use std::{
+    collections::VecDeque,
+    fmt,
+    future::{poll_fn, Future},
+    pin::Pin,
+    sync::Arc,
+    task::Poll,
+};
+
+/// Iterator returned by
+/// [`StreamExt::map_parallel_blocking`](super::StreamExt::map_parallel_blocking).
+pub struct MapParallelBlocking<S, F, Fut: Future> {
+    // `None` once the stream has ended.
+    stream: Option<S>,
+    f: Arc<F>,
+    limit: usize,
+    pool: ThreadPool,
+    // Handles in stream order, finished or not.
+    running: VecDeque<JoinHandle<Fut::Output>>,
+}
+
+impl<S, F, Fut> MapParallelBlocking<S, F, Fut>
+where
+    S: Stream,
+    F: Fn(S::Item) -> Fut,
+    Fut: Future,
+{
+    pub(super) fn new(stream: S, limit: usize, f: F) -> Self {
+        assert!(limit > 0, "map_parallel_blocking limit must be non-zero");
+        let pool = ThreadPool::builder()
+            .pool_size(limit)
+            .name_prefix("pollster-map-")
+            .create()
+            .expect("failed to spawn map_parallel_blocking threads");
+        Self {
+            stream: Some(stream),
+            f: Arc::new(f),
+            limit,
+            pool,
+            running: VecDeque::with_capacity(limit),
+        }
+    }
+}
+
+impl<S, F, Fut> Iterator for MapParallelBlocking<S, F, Fut>
+where
+    S: Stream + Unpin,
+    S::Item: Send + 'static,
+    F: Fn(S::Item) -> Fut + Send + Sync + 'static,
+    Fut: Future + Send + 'static,
+    Fut::Output: Send + 'static,
+{
+    type Item = Fut::Output;
+
+    fn next(&mut self) -> Option<Fut::Output> {
+        crate::block_on(poll_fn(|cx| {
+            while self.running.len() < self.limit {
+                let item = match &mut self.stream {
+                    Some(stream) => Pin::new(stream).poll_next(cx),
+                    None => break,
+                };
+                match item {
+                    Poll::Ready(Some(item)) => {
+                        // The mapping itself runs on the worker, so heavy synchronous work in it
+                        // doesn't hold up the caller.
+                        let f = Arc::clone(&self.f);
+                        self.running.push_back(self.pool.spawn(async move { f(item).await }));
+                    }
+                    Poll::Ready(None) => self.stream = None,
+                    Poll::Pending => break,
+                }
+            }
+
+            match self.running.front_mut() {
+                Some(handle) => match Pin::new(handle).poll(cx) {
+                    Poll::Ready(output) => {
+                        self.running.pop_front();
+                        Poll::Ready(Some(output))
+                    }
+                    Poll::Pending => Poll::Pending,
+                },
+                None if self.stream.is_none() => Poll::Ready(None),
+                None => Poll::Pending,
+            }
+        }))
+    }
+}
+
+impl<S, F, Fut: Future> fmt::Debug for MapParallelBlocking<S, F, Fut> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapParallelBlocking")
+            .field("limit", &self.limit)
+            .field("running", &self.running.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,109 @@
+use futures_core::Stream;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Combine two streams into one that yields items from either as soon as they are ready, ending
+/// once both have ended.
+///
+/// Each poll tries both streams, starting with a different one each time so a busy stream can't
+/// starve the other.
+///
+/// # Example
+///
+/// ```
+/// use pollster::stream::{block_on_stream, merge};
+///
+/// let evens = pollster::iter::into_stream(vec![0, 2, 4]);
+/// let odds = pollster::iter::into_stream(vec![1, 3]);
+/// let mut items: Vec<_> = block_on_stream(merge(evens, odds)).collect();
+/// items.sort();
+/// assert_eq!(items, [0, 1, 2, 3, 4]);
+/// ```
+pub fn merge<A, B>(a: A, b: B) -> Merge<A, B>
+where
+    A: Stream,
+    B: Stream<Item = A::Item>,
+{
+    Merge {
+        a,
+        b,
+        a_done: false,
+        b_done: false,
+        b_first: false,
+    }
+}
+
+/// Stream returned by [`merge`].
+#[derive(Debug)]
+pub struct Merge<A, B> {
+    a: A,
+    b: B,
+    a_done: bool,
+    b_done: bool,
+    // Which stream gets polled first next time.
+    b_first: bool,
+}
+
+impl<A, B> Merge<A, B> {
+    /// Unwrap the two streams.
+    pub fn into_inner(self) -> (A, B) {
+        (self.a, self.b)
+    }
+}
+
+impl<A, B> Stream for Merge<A, B>
+where
+    A: Stream,
+    B: Stream<Item = A::Item>,
+{
+    type Item = A::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<A::Item>> {
+        // SAFETY: `a` and `b` are never moved out of `self`; the flags are not structurally
+        // pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        this.b_first = !this.b_first;
+        for poll_b in [!this.b_first, this.b_first] {
+            let item = if poll_b {
+                if this.b_done {
+                    continue;
+                }
+                // SAFETY: `Merge` has no `Drop` impl and is only `Unpin` when the `b` it wraps is,
+                // so `b` stays pinned for as long as `self` is.
+                let item = unsafe { Pin::new_unchecked(&mut this.b) }.poll_next(cx);
+                this.b_done = matches!(item, Poll::Ready(None));
+                item
+            } else {
+                if this.a_done {
+                    continue;
+                }
+                // SAFETY: `Merge` has no `Drop` impl and is only `Unpin` when the `a` it wraps is,
+                // so `a` stays pinned for as long as `self` is.
+                let item = unsafe { Pin::new_unchecked(&mut this.a) }.poll_next(cx);
+                this.a_done = matches!(item, Poll::Ready(None));
+                item
+            };
+            if let Poll::Ready(Some(item)) = item {
+                return Poll::Ready(Some(item));
+            }
+        }
+
+        if this.a_done && this.b_done {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a_low, a_high) = if self.a_done { (0, Some(0)) } else { self.a.size_hint() };
+        let (b_low, b_high) = if self.b_done { (0, Some(0)) } else { self.b.size_hint() };
+        let high = match (a_high, b_high) {
+            (Some(a), Some(b)) => a.checked_add(b),
+            _ => None,
+        };
+        (a_low.saturating_add(b_low), high)
+    }
+}
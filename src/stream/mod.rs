@@ -0,0 +1,334 @@
+//! Driving and combining [`Stream`]s from blocking code.
+
+mod buffered;
+mod chunks;
+mod debounce;
+mod map_parallel;
+mod merge;
+mod tee;
+mod throttle;
+mod timeout;
+mod zip;
+
+pub use buffered::{BufferUnorderedBlocking, BufferedBlocking};
+pub use chunks::ChunksTimeout;
+pub use debounce::Debounce;
+pub use map_parallel::MapParallelBlocking;
+pub use merge::{merge, Merge};
+pub use tee::{tee, tee_with_capacity, Tee};
+pub use throttle::Throttle;
+pub use timeout::Timeout;
+pub use zip::{zip, Zip};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+use std::{
+    future::{poll_fn, Future},
+    pin::{pin, Pin},
+    task::{ready, Poll},
+    time::Duration,
+};
+
+/// Turn a stream into a blocking iterator, blocking the current thread on every item.
+///
+/// # Example
+///
+/// ```
+/// let items: Vec<_> = pollster::stream::block_on_stream(pollster::iter::into_stream(1..=3)).collect();
+/// assert_eq!(items, [1, 2, 3]);
+/// ```
+pub fn block_on_stream<S: Stream + Unpin>(stream: S) -> BlockingStream<S> {
+    BlockingStream { stream }
+}
+
+/// Iterator returned by [`block_on_stream`].
+#[derive(Debug)]
+pub struct BlockingStream<S> {
+    stream: S,
+}
+
+impl<S> BlockingStream<S> {
+    /// Unwrap the stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: Stream + Unpin> Iterator for BlockingStream<S> {
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        let stream = &mut self.stream;
+        crate::block_on(poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+/// Block the current thread while sending every item of `stream` into `sink`, then flush and close
+/// the sink.
+///
+/// Both ends are driven concurrently: the sink is flushed whenever the stream has nothing ready,
+/// so items don't sit in its buffer while the stream waits. Returns the number of items sent, or
+/// the first error from the sink.
+///
+/// # Example
+///
+/// ```
+/// use std::{convert::Infallible, pin::Pin, task::{Context, Poll}};
+///
+/// struct Collect(Vec<u32>);
+///
+/// impl futures_sink::Sink<u32> for Collect {
+///     type Error = Infallible;
+///
+///     fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+///         Poll::Ready(Ok(()))
+///     }
+///     fn start_send(mut self: Pin<&mut Self>, item: u32) -> Result<(), Infallible> {
+///         self.0.push(item);
+///         Ok(())
+///     }
+///     fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+///         Poll::Ready(Ok(()))
+///     }
+///     fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+///         Poll::Ready(Ok(()))
+///     }
+/// }
+///
+/// let mut sink = Collect(Vec::new());
+/// let sent = pollster::stream::forward_blocking(pollster::iter::into_stream(1..=3), &mut sink);
+/// assert_eq!(sent, Ok(3));
+/// assert_eq!(sink.0, [1, 2, 3]);
+/// ```
+#[track_caller]
+pub fn forward_blocking<S, Si>(stream: S, sink: Si) -> Result<usize, Si::Error>
+where
+    S: Stream,
+    Si: Sink<S::Item>,
+{
+    let (mut stream, mut sink) = (pin!(stream), pin!(sink));
+    let mut buffered = None;
+    let mut sent = 0;
+    let mut ended = false;
+    crate::block_on(poll_fn(|cx| loop {
+        if ended {
+            ready!(sink.as_mut().poll_close(cx))?;
+            return Poll::Ready(Ok(sent));
+        }
+        if buffered.is_some() {
+            ready!(sink.as_mut().poll_ready(cx))?;
+            sink.as_mut().start_send(buffered.take().unwrap())?;
+            sent += 1;
+        }
+        match stream.as_mut().poll_next(cx) {
+            Poll::Ready(Some(item)) => buffered = Some(item),
+            Poll::Ready(None) => ended = true,
+            Poll::Pending => {
+                ready!(sink.as_mut().poll_flush(cx))?;
+                return Poll::Pending;
+            }
+        }
+    }))
+}
+
+/// Combinators for [`Stream`]s.
+pub trait StreamExt: Stream {
+    /// Require every item to arrive within `per_item` of the stream being polled for it.
+    ///
+    /// When the deadline passes, an [`Elapsed`](crate::time::Elapsed) error is yielded instead
+    /// and a fresh deadline starts for the next item. The stream itself is left untouched.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use pollster::stream::{block_on_stream, StreamExt as _};
+    ///
+    /// let stream = pollster::iter::into_stream(vec![1, 2]).timeout(Duration::from_secs(10));
+    /// let items: Vec<_> = block_on_stream(stream).collect();
+    /// assert_eq!(items, [Ok(1), Ok(2)]);
+    /// ```
+    fn timeout(self, per_item: Duration) -> Timeout<Self>
+    where
+        Self: Sized,
+    {
+        Timeout::new(self, per_item)
+    }
+
+    /// Yield at most one item per `period`, delaying items that arrive sooner.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use pollster::stream::{block_on_stream, StreamExt as _};
+    ///
+    /// let start = Instant::now();
+    /// let stream = pollster::iter::into_stream(0..3).throttle(Duration::from_millis(10));
+    /// assert_eq!(block_on_stream(stream).count(), 3);
+    /// assert!(start.elapsed() >= Duration::from_millis(20));
+    /// ```
+    fn throttle(self, period: Duration) -> Throttle<Self>
+    where
+        Self: Sized,
+    {
+        Throttle::new(self, period)
+    }
+
+    /// Only yield an item once `quiet_period` has passed without a newer one arriving.
+    ///
+    /// Items that are replaced within the quiet period are dropped. When the stream ends, the
+    /// last item is yielded straight away. A stream that never pauses never yields, but the task
+    /// still gets to do other work, like time out, between batches of its items.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{future::poll_fn, pin::Pin, time::Duration};
+    /// use futures_core::Stream;
+    /// use pollster::stream::{block_on_stream, StreamExt as _};
+    ///
+    /// // A burst of updates settles on the last one.
+    /// let stream = pollster::iter::into_stream(0..100).debounce(Duration::from_secs(10));
+    /// assert_eq!(block_on_stream(stream).collect::<Vec<_>>(), [99]);
+    ///
+    /// let mut endless = pollster::iter::into_stream(0..).debounce(Duration::from_millis(1));
+    /// let next = poll_fn(|cx| Pin::new(&mut endless).poll_next(cx));
+    /// assert!(pollster::block_on(pollster::time::timeout(Duration::from_millis(20), next)).is_err());
+    /// ```
+    fn debounce(self, quiet_period: Duration) -> Debounce<Self>
+    where
+        Self: Sized,
+    {
+        Debounce::new(self, quiet_period)
+    }
+
+    /// Collect items into chunks, yielding a chunk once it holds `max_items` or once `max_wait`
+    /// has passed since its first item arrived, whichever comes first.
+    ///
+    /// Chunks are never empty. When the stream ends, the items collected so far are yielded as a
+    /// final, possibly shorter, chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_items` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use pollster::stream::{block_on_stream, StreamExt as _};
+    ///
+    /// let stream = pollster::iter::into_stream(0..7).chunks_timeout(3, Duration::from_secs(10));
+    /// let chunks: Vec<_> = block_on_stream(stream).collect();
+    /// assert_eq!(chunks, [vec![0, 1, 2], vec![3, 4, 5], vec![6]]);
+    /// ```
+    fn chunks_timeout(self, max_items: usize, max_wait: Duration) -> ChunksTimeout<Self>
+    where
+        Self: Sized,
+    {
+        ChunksTimeout::new(self, max_items, max_wait)
+    }
+
+    /// Block on a stream of futures, running up to `limit` of them at once and yielding their
+    /// outputs in stream order.
+    ///
+    /// The futures only make progress while the iterator is being advanced.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use pollster::stream::StreamExt as _;
+    ///
+    /// let requests = pollster::iter::into_stream((1..=4).map(|i| async move {
+    ///     pollster::time::sleep(Duration::from_millis(10 * (5 - i))).await;
+    ///     i
+    /// }));
+    /// let responses: Vec<_> = requests.buffered_blocking(2).collect();
+    /// assert_eq!(responses, [1, 2, 3, 4]);
+    /// ```
+    fn buffered_blocking(self, limit: usize) -> BufferedBlocking<Self>
+    where
+        Self: Sized + Unpin,
+        Self::Item: Future,
+    {
+        BufferedBlocking::new(self, limit)
+    }
+
+    /// Like [`buffered_blocking`](StreamExt::buffered_blocking), but yield outputs as soon as
+    /// they are ready rather than in stream order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use pollster::stream::StreamExt as _;
+    ///
+    /// let requests = pollster::iter::into_stream(vec![30, 10].into_iter().map(|ms| async move {
+    ///     pollster::time::sleep(Duration::from_millis(ms)).await;
+    ///     ms
+    /// }));
+    /// let responses: Vec<_> = requests.buffer_unordered_blocking(2).collect();
+    /// assert_eq!(responses, [10, 30]);
+    /// ```
+    fn buffer_unordered_blocking(self, limit: usize) -> BufferUnorderedBlocking<Self>
+    where
+        Self: Sized + Unpin,
+        Self::Item: Future,
+    {
+        BufferUnorderedBlocking::new(self, limit)
+    }
+
+    /// Map every item with `f` on a pool of `limit` worker threads, running up to `limit` of the
+    /// mappings at once and yielding their outputs in stream order.
+    ///
+    /// `f` is called on a worker, and so is the future it returns polled, so a mapping can be
+    /// async or do heavy synchronous work before returning. The workers are started when this is
+    /// called and exit once the iterator is dropped. If a mapping panics, the panic is resumed
+    /// when its output would have been yielded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is 0, or if the worker threads can't be started.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use pollster::stream::StreamExt as _;
+    ///
+    /// let lines = pollster::iter::into_stream(vec!["3", "1", "4", "1", "5"]);
+    /// let parsed: Vec<u64> = lines
+    ///     .map_parallel_blocking(3, |line| async move {
+    ///         // Stand-in for CPU-heavy work.
+    ///         let n: u64 = line.parse().unwrap();
+    ///         (1..=n).product()
+    ///     })
+    ///     .collect();
+    /// assert_eq!(parsed, [6, 1, 24, 1, 120]);
+    /// ```
+    fn map_parallel_blocking<F, Fut>(self, limit: usize, f: F) -> MapParallelBlocking<Self, F, Fut>
+    where
+        Self: Sized + Unpin,
+        Self::Item: Send + 'static,
+        F: Fn(Self::Item) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: Send + 'static,
+    {
+        MapParallelBlocking::new(self, limit, f)
+    }
+}
+
+impl<S: Stream + ?Sized> StreamExt for S {}
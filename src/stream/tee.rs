@@ -0,0 +1,198 @@
+use futures_core::Stream;
+use std::{
+    collections::VecDeque,
+    fmt,
+    future::poll_fn,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread,
+};
+
+const DEFAULT_CAPACITY: usize = 32;
+
+struct State<S: Stream> {
+    // `None` while one of the iterators is blocked on it.
+    stream: Option<S>,
+    done: bool,
+    buffer: VecDeque<S::Item>,
+    // The index of `buffer[0]` since the stream started.
+    base: u64,
+    // The index of the next item for each iterator, or `None` once it is dropped.
+    positions: Vec<Option<u64>>,
+}
+
+impl<S: Stream> State<S> {
+    // Drop the items every live iterator has read past.
+    fn trim(&mut self) -> bool {
+        let oldest = self.positions.iter().flatten().min().copied().unwrap_or(u64::MAX);
+        let mut trimmed = false;
+        while self.base < oldest && self.buffer.pop_front().is_some() {
+            self.base += 1;
+            trimmed = true;
+        }
+        trimmed
+    }
+}
+
+struct Shared<S: Stream> {
+    state: Mutex<State<S>>,
+    cond: Condvar,
+    capacity: usize,
+}
+
+/// Split a stream into `n` blocking iterators that each yield every item, buffering up to 32
+/// items that not every iterator has reached yet.
+///
+/// See [`tee_with_capacity`].
+pub fn tee<S>(stream: S, n: usize) -> Vec<Tee<S>>
+where
+    S: Stream + Unpin,
+    S::Item: Clone,
+{
+    tee_with_capacity(stream, n, DEFAULT_CAPACITY)
+}
+
+/// Split a stream into `n` blocking iterators that each yield every item, buffering up to
+/// `capacity` items that not every iterator has reached yet.
+///
+/// The iterators can be moved to different threads. Whichever one runs out of buffered items
+/// blocks on the stream for the next one, on its own thread, while the others wait. Once the
+/// fastest iterator is `capacity` items ahead of the slowest, it waits for the slowest to catch
+/// up, so a lagging consumer slows the stream down instead of growing the buffer. Dropping an
+/// iterator stops it from holding the others back.
+///
+/// # Panics
+///
+/// Panics if `capacity` is 0.
+///
+/// # Example
+///
+/// ```
+/// use std::thread;
+///
+/// let events = pollster::iter::into_stream(1..=100);
+/// let workers: Vec<_> = pollster::stream::tee_with_capacity(events, 3, 8)
+///     .into_iter()
+///     .map(|events| thread::spawn(move || events.sum::<u32>()))
+///     .collect();
+/// for worker in workers {
+///     assert_eq!(worker.join().unwrap(), 5050);
+/// }
+/// ```
+pub fn tee_with_capacity<S>(stream: S, n: usize, capacity: usize) -> Vec<Tee<S>>
+where
+    S: Stream + Unpin,
+    S::Item: Clone,
+{
+    assert!(capacity > 0, "tee capacity must be non-zero");
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            stream: Some(stream),
+            done: false,
+            buffer: VecDeque::with_capacity(capacity),
+            base: 0,
+            positions: vec![Some(0); n],
+        }),
+        cond: Condvar::new(),
+        capacity,
+    });
+    (0..n)
+        .map(|index| Tee {
+            shared: Arc::clone(&shared),
+            index,
+            position: 0,
+        })
+        .collect()
+}
+
+/// One of the iterators returned by [`tee`] and [`tee_with_capacity`].
+pub struct Tee<S: Stream> {
+    shared: Arc<Shared<S>>,
+    index: usize,
+    position: u64,
+}
+
+impl<S: Stream + Unpin> Shared<S> {
+    // Block on the stream for the next item, with the lock released meanwhile.
+    fn pull<'a>(&'a self, mut state: MutexGuard<'a, State<S>>, mut stream: S) -> MutexGuard<'a, State<S>> {
+        // If the stream panics, end the other iterators rather than leaving them waiting for it.
+        struct Guard<'a, S: Stream>(&'a Shared<S>);
+
+        impl<S: Stream> Drop for Guard<'_, S> {
+            fn drop(&mut self) {
+                if thread::panicking() {
+                    if let Ok(mut state) = self.0.state.lock() {
+                        state.done = true;
+                    }
+                    self.0.cond.notify_all();
+                }
+            }
+        }
+
+        drop(state);
+        let guard = Guard(self);
+        let item = crate::block_on(poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)));
+        drop(guard);
+
+        state = self.state.lock().unwrap();
+        state.stream = Some(stream);
+        match item {
+            Some(item) => state.buffer.push_back(item),
+            None => state.done = true,
+        }
+        self.cond.notify_all();
+        state
+    }
+}
+
+impl<S: Stream + Unpin> Iterator for Tee<S>
+where
+    S::Item: Clone,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        let shared = &*self.shared;
+        let mut state = shared.state.lock().unwrap();
+        loop {
+            let offset = (self.position - state.base) as usize;
+            if let Some(item) = state.buffer.get(offset) {
+                let item = item.clone();
+                self.position += 1;
+                state.positions[self.index] = Some(self.position);
+                if state.trim() {
+                    shared.cond.notify_all();
+                }
+                return Some(item);
+            } else if state.done {
+                return None;
+            } else if state.buffer.len() < shared.capacity {
+                if let Some(stream) = state.stream.take() {
+                    state = shared.pull(state, stream);
+                    continue;
+                }
+            }
+            state = shared.cond.wait(state).unwrap();
+        }
+    }
+}
+
+impl<S: Stream> Drop for Tee<S> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.positions[self.index] = None;
+            if state.trim() {
+                self.shared.cond.notify_all();
+            }
+        }
+    }
+}
+
+impl<S: Stream> fmt::Debug for Tee<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tee")
+            .field("index", &self.index)
+            .field("position", &self.position)
+            .finish_non_exhaustive()
+    }
+}
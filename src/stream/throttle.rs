@@ -0,0 +1,59 @@
+use crate::time::{self, Sleep};
+use futures_core::Stream;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+/// Stream returned by [`StreamExt::throttle`](super::StreamExt::throttle).
+#[derive(Debug)]
+pub struct Throttle<S> {
+    stream: S,
+    period: Duration,
+    // Running between an item being yielded and the next one being allowed through.
+    sleep: Option<Sleep>,
+}
+
+impl<S> Throttle<S> {
+    pub(super) fn new(stream: S, period: Duration) -> Self {
+        Self {
+            stream,
+            period,
+            sleep: None,
+        }
+    }
+
+    /// Unwrap the stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: Stream> Stream for Throttle<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        // SAFETY: `stream` is never moved out of `self`; `sleep` is `Unpin`.
+        let this = unsafe { self.get_unchecked_mut() };
+        if let Some(sleep) = &mut this.sleep {
+            if Pin::new(sleep).poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            this.sleep = None;
+        }
+
+        // SAFETY: `Throttle` has no `Drop` impl and is only `Unpin` when the `stream` it wraps is,
+        // so `stream` stays pinned for as long as `self` is.
+        let item = unsafe { Pin::new_unchecked(&mut this.stream) }.poll_next(cx);
+        if let Poll::Ready(Some(_)) = item {
+            this.sleep = Some(time::sleep(this.period));
+        }
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
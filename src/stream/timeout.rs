@@ -0,0 +1,63 @@
+use crate::time::{self, Elapsed, Sleep};
+use futures_core::Stream;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+/// Stream returned by [`StreamExt::timeout`](super::StreamExt::timeout).
+#[derive(Debug)]
+pub struct Timeout<S> {
+    stream: S,
+    per_item: Duration,
+    // Armed when the stream first returns `Pending` after yielding an item.
+    sleep: Option<Sleep>,
+}
+
+impl<S> Timeout<S> {
+    pub(super) fn new(stream: S, per_item: Duration) -> Self {
+        Self {
+            stream,
+            per_item,
+            sleep: None,
+        }
+    }
+
+    /// Unwrap the stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: Stream> Stream for Timeout<S> {
+    type Item = Result<S::Item, Elapsed>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // SAFETY: `stream` is never moved out of `self`; `sleep` is `Unpin`.
+        let this = unsafe { self.get_unchecked_mut() };
+        // SAFETY: `Timeout` has no `Drop` impl and is only `Unpin` when the `stream` it wraps is,
+        // so `stream` stays pinned for as long as `self` is.
+        if let Poll::Ready(item) = unsafe { Pin::new_unchecked(&mut this.stream) }.poll_next(cx) {
+            this.sleep = None;
+            return Poll::Ready(item.map(Ok));
+        }
+
+        let per_item = this.per_item;
+        let sleep = this.sleep.get_or_insert_with(|| time::sleep(per_item));
+        match Pin::new(&mut *sleep).poll(cx) {
+            Poll::Ready(()) => {
+                let shut_down = sleep.is_shut_down();
+                this.sleep = None;
+                Poll::Ready(Some(Err(Elapsed::new(shut_down))))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, _) = self.stream.size_hint();
+        (lower, None)
+    }
+}
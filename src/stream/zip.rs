@@ -0,0 +1,111 @@
+use futures_core::Stream;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Combine two streams into one that yields their items in pairs, ending as soon as either ends.
+///
+/// Both streams are polled concurrently. An item that arrives before its partner is held until
+/// the other stream produces one.
+///
+/// # Example
+///
+/// ```
+/// use pollster::stream::{block_on_stream, zip};
+///
+/// let names = pollster::iter::into_stream(vec!["a", "b", "c"]);
+/// let scores = pollster::iter::into_stream(vec![3, 1]);
+/// let pairs: Vec<_> = block_on_stream(zip(names, scores)).collect();
+/// assert_eq!(pairs, [("a", 3), ("b", 1)]);
+/// ```
+pub fn zip<A: Stream, B: Stream>(a: A, b: B) -> Zip<A, B> {
+    Zip {
+        a,
+        b,
+        a_item: None,
+        b_item: None,
+        done: false,
+    }
+}
+
+/// Stream returned by [`zip`].
+#[derive(Debug)]
+pub struct Zip<A: Stream, B: Stream> {
+    a: A,
+    b: B,
+    // Items waiting for their partner from the other stream.
+    a_item: Option<A::Item>,
+    b_item: Option<B::Item>,
+    done: bool,
+}
+
+impl<A: Stream, B: Stream> Zip<A, B> {
+    /// Unwrap the two streams, discarding any item still waiting for its partner.
+    pub fn into_inner(self) -> (A, B) {
+        (self.a, self.b)
+    }
+}
+
+impl<A: Stream, B: Stream> Stream for Zip<A, B> {
+    type Item = (A::Item, B::Item);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // SAFETY: `a` and `b` are never moved out of `self`; the other fields are not
+        // structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        if this.a_item.is_none() {
+            // SAFETY: `Zip` has no `Drop` impl and is only `Unpin` when the `a` it wraps is, so `a`
+            // stays pinned for as long as `self` is.
+            match unsafe { Pin::new_unchecked(&mut this.a) }.poll_next(cx) {
+                Poll::Ready(Some(item)) => this.a_item = Some(item),
+                Poll::Ready(None) => this.done = true,
+                Poll::Pending => {}
+            }
+        }
+        if this.b_item.is_none() && !this.done {
+            // SAFETY: `Zip` has no `Drop` impl and is only `Unpin` when the `b` it wraps is, so `b`
+            // stays pinned for as long as `self` is.
+            match unsafe { Pin::new_unchecked(&mut this.b) }.poll_next(cx) {
+                Poll::Ready(Some(item)) => this.b_item = Some(item),
+                Poll::Ready(None) => this.done = true,
+                Poll::Pending => {}
+            }
+        }
+
+        if this.done {
+            this.a_item = None;
+            this.b_item = None;
+            return Poll::Ready(None);
+        }
+        match (this.a_item.take(), this.b_item.take()) {
+            (Some(a), Some(b)) => Poll::Ready(Some((a, b))),
+            (a, b) => {
+                this.a_item = a;
+                this.b_item = b;
+                Poll::Pending
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            return (0, Some(0));
+        }
+        let buffered = |item: bool, (low, high): (usize, Option<usize>)| {
+            let extra = usize::from(item);
+            (low.saturating_add(extra), high.and_then(|high| high.checked_add(extra)))
+        };
+        let (a_low, a_high) = buffered(self.a_item.is_some(), self.a.size_hint());
+        let (b_low, b_high) = buffered(self.b_item.is_some(), self.b.size_hint());
+        let high = match (a_high, b_high) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (high, None) | (None, high) => high,
+        };
+        (a_low.min(b_low), high)
+    }
+}
@@ -0,0 +1,596 @@
+//! A bounded multi-producer, multi-consumer channel that keeps its buffer inline, for code that
+//! can't or won't allocate.
+//!
+//! An [`ArrayChannel`] holds up to `N` values in place and has no separate halves: senders and
+//! receivers share a reference to it, so it usually lives in a `static` or in a scope that
+//! outlives its users. Waiting tasks are tracked through their own futures and blocked threads
+//! wait on condition variables, so sending and receiving never allocate.
+//!
+//! # Example
+//!
+//! ```
+//! use pollster::sync::array_channel::ArrayChannel;
+//!
+//! static JOBS: ArrayChannel<u32, 4> = ArrayChannel::new();
+//!
+//! let worker = std::thread::spawn(|| {
+//!     let mut total = 0;
+//!     while let Ok(job) = JOBS.recv_blocking() {
+//!         total += job;
+//!     }
+//!     total
+//! });
+//! for job in 1..=10 {
+//!     pollster::block_on(JOBS.send(job)).unwrap();
+//! }
+//! JOBS.close();
+//! assert_eq!(worker.join().unwrap(), 55);
+//! ```
+
+use std::{
+    cell::UnsafeCell,
+    error::Error,
+    fmt,
+    future::Future,
+    marker::PhantomPinned,
+    pin::Pin,
+    ptr,
+    sync::{Condvar, Mutex, MutexGuard},
+    task::{ready, Context, Poll, Waker},
+};
+
+// A task waiting for space or for a value, stored in its own future.
+struct Waiter {
+    waker: Option<Waker>,
+    prev: *mut Waiter,
+    next: *mut Waiter,
+    // In the list, waiting to be woken.
+    queued: bool,
+    // Woken for a change it hasn't acted on yet, so it passes that on if dropped.
+    notified: bool,
+}
+
+impl Waiter {
+    const fn new() -> Self {
+        Self {
+            waker: None,
+            prev: ptr::null_mut(),
+            next: ptr::null_mut(),
+            queued: false,
+            notified: false,
+        }
+    }
+}
+
+// An intrusive list of waiters, only touched with the channel's lock held.
+struct List {
+    head: *mut Waiter,
+    tail: *mut Waiter,
+}
+
+impl List {
+    const fn new() -> Self {
+        Self {
+            head: ptr::null_mut(),
+            tail: ptr::null_mut(),
+        }
+    }
+
+    // SAFETY: `node` must be valid and not already queued.
+    unsafe fn push(&mut self, node: *mut Waiter) {
+        (*node).prev = self.tail;
+        (*node).next = ptr::null_mut();
+        if self.tail.is_null() {
+            self.head = node;
+        } else {
+            (*self.tail).next = node;
+        }
+        self.tail = node;
+        (*node).queued = true;
+    }
+
+    // SAFETY: `node` must be queued in this list.
+    unsafe fn remove(&mut self, node: *mut Waiter) {
+        let (prev, next) = ((*node).prev, (*node).next);
+        if prev.is_null() {
+            self.head = next;
+        } else {
+            (*prev).next = next;
+        }
+        if next.is_null() {
+            self.tail = prev;
+        } else {
+            (*next).prev = prev;
+        }
+        (*node).queued = false;
+    }
+
+    // Unlink the first waiter and mark it notified, returning its waker.
+    fn pop(&mut self) -> Option<Waker> {
+        if self.head.is_null() {
+            return None;
+        }
+        let node = self.head;
+        // SAFETY: queued waiters stay valid until they unlink themselves, which takes the lock.
+        unsafe {
+            self.remove(node);
+            (*node).notified = true;
+            (*node).waker.take()
+        }
+    }
+}
+
+struct State<T, const N: usize> {
+    buf: [Option<T>; N],
+    head: usize,
+    len: usize,
+    closed: bool,
+    senders: List,
+    receivers: List,
+}
+
+// SAFETY: the waiter pointers are only followed with the channel's lock held, and every waiter
+// unlinks itself under the lock before its future is dropped.
+unsafe impl<T: Send, const N: usize> Send for State<T, N> {}
+
+impl<T, const N: usize> State<T, N> {
+    fn push(&mut self, value: T) -> Result<(), T> {
+        if self.len == N {
+            return Err(value);
+        }
+        self.buf[(self.head + self.len) % N] = Some(value);
+        self.len += 1;
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let value = self.buf[self.head].take();
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        value
+    }
+}
+
+/// A bounded channel storing up to `N` values inline. See the [module docs](self).
+pub struct ArrayChannel<T, const N: usize> {
+    state: Mutex<State<T, N>>,
+    not_full: Condvar,
+    not_empty: Condvar,
+}
+
+impl<T, const N: usize> ArrayChannel<T, N> {
+    const EMPTY: Option<T> = None;
+
+    /// Create an empty channel. This is a `const fn`, so the channel can be a `static`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is 0.
+    pub const fn new() -> Self {
+        assert!(N > 0, "array channel capacity must be non-zero");
+        Self {
+            state: Mutex::new(State {
+                buf: [Self::EMPTY; N],
+                head: 0,
+                len: 0,
+                closed: false,
+                senders: List::new(),
+                receivers: List::new(),
+            }),
+            not_full: Condvar::new(),
+            not_empty: Condvar::new(),
+        }
+    }
+
+    /// How many values the channel can hold.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// How many values are waiting to be received.
+    pub fn len(&self) -> usize {
+        self.lock().len
+    }
+
+    /// Whether no values are waiting to be received.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Send a value if there is room for it, without waiting.
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let mut state = self.lock();
+        if state.closed {
+            return Err(TrySendError::Closed(value));
+        }
+        state.push(value).map_err(TrySendError::Full)?;
+        self.sent(state);
+        Ok(())
+    }
+
+    /// Send a value asynchronously, waiting for room if the channel is full.
+    ///
+    /// Fails if the channel is closed before the value could be sent.
+    pub fn send(&self, value: T) -> SendValue<'_, T, N> {
+        SendValue {
+            channel: self,
+            value: Some(value),
+            waiter: UnsafeCell::new(Waiter::new()),
+            done: false,
+            _pinned: PhantomPinned,
+        }
+    }
+
+    /// Block the current thread until there is room for the value, then send it.
+    pub fn send_blocking(&self, value: T) -> Result<(), SendError<T>> {
+        let mut state = self.lock();
+        let mut value = value;
+        loop {
+            if state.closed {
+                return Err(SendError(value));
+            }
+            match state.push(value) {
+                Ok(()) => break,
+                Err(unsent) => value = unsent,
+            }
+            state = self.not_full.wait(state).unwrap();
+        }
+        self.sent(state);
+        Ok(())
+    }
+
+    /// Receive a value if one is waiting, without blocking.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut state = self.lock();
+        match state.pop() {
+            Some(value) => {
+                self.received(state);
+                Ok(value)
+            }
+            None if state.closed => Err(TryRecvError::Closed),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Receive a value asynchronously.
+    ///
+    /// Fails once the channel is closed and every value sent before that has been received.
+    pub fn recv(&self) -> RecvValue<'_, T, N> {
+        RecvValue {
+            channel: self,
+            waiter: UnsafeCell::new(Waiter::new()),
+            done: false,
+            _pinned: PhantomPinned,
+        }
+    }
+
+    /// Block the current thread until a value arrives.
+    pub fn recv_blocking(&self) -> Result<T, RecvError> {
+        let mut state = self.lock();
+        loop {
+            if let Some(value) = state.pop() {
+                self.received(state);
+                return Ok(value);
+            } else if state.closed {
+                return Err(RecvError(()));
+            }
+            state = self.not_empty.wait(state).unwrap();
+        }
+    }
+
+    /// Close the channel. Sends fail from now on, while receives still get the values already
+    /// sent. Everything waiting on the channel is woken.
+    pub fn close(&self) {
+        self.lock().closed = true;
+        self.not_full.notify_all();
+        self.not_empty.notify_all();
+        // One at a time, so that waking happens outside the lock without collecting the wakers.
+        loop {
+            let mut state = self.lock();
+            let waker = state.senders.pop().or_else(|| state.receivers.pop());
+            drop(state);
+            match waker {
+                Some(waker) => waker.wake(),
+                None => break,
+            }
+        }
+    }
+
+    /// Whether [`close`](ArrayChannel::close) has been called.
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<T, N>> {
+        self.state.lock().unwrap()
+    }
+
+    // Tell one waiting receiver about a value that was just sent.
+    fn sent(&self, mut state: MutexGuard<'_, State<T, N>>) {
+        let waker = state.receivers.pop();
+        drop(state);
+        self.not_empty.notify_one();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    // Tell one waiting sender about the room that was just made.
+    fn received(&self, mut state: MutexGuard<'_, State<T, N>>) {
+        let waker = state.senders.pop();
+        drop(state);
+        self.not_full.notify_one();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T, const N: usize> Default for ArrayChannel<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for ArrayChannel<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.lock();
+        f.debug_struct("ArrayChannel")
+            .field("len", &state.len)
+            .field("capacity", &N)
+            .field("closed", &state.closed)
+            .finish()
+    }
+}
+
+// Queue `waiter` on `list` to be woken through `cx`, or just refresh its waker if it is queued.
+//
+// SAFETY: `waiter` must be pinned, and must unlink itself before it is dropped.
+unsafe fn wait(list: &mut List, waiter: *mut Waiter, cx: &Context<'_>) {
+    match &(*waiter).waker {
+        Some(waker) if waker.will_wake(cx.waker()) => {}
+        _ => (*waiter).waker = Some(cx.waker().clone()),
+    }
+    if !(*waiter).queued {
+        list.push(waiter);
+    }
+}
+
+/// Future returned by [`ArrayChannel::send`].
+pub struct SendValue<'a, T, const N: usize> {
+    channel: &'a ArrayChannel<T, N>,
+    value: Option<T>,
+    waiter: UnsafeCell<Waiter>,
+    // Completed, so no longer linked or owed a wakeup.
+    done: bool,
+    _pinned: PhantomPinned,
+}
+
+// SAFETY: the waiter is only touched with the channel's lock held.
+unsafe impl<T: Send, const N: usize> Send for SendValue<'_, T, N> {}
+
+impl<T, const N: usize> Future for SendValue<'_, T, N> {
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        ready!(crate::coop::poll_proceed(cx));
+        // SAFETY: nothing is moved out of `self` but `value`, which is never pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        let channel = this.channel;
+        let waiter = this.waiter.get();
+        let mut state = channel.lock();
+        // SAFETY: the lock is held, and the future is pinned until it unlinks in `drop`.
+        unsafe {
+            (*waiter).notified = false;
+            let value = this.value.take().expect("`SendValue` polled after completion");
+            let result = if state.closed {
+                Err(SendError(value))
+            } else {
+                match state.push(value) {
+                    Ok(()) => Ok(()),
+                    Err(value) => {
+                        this.value = Some(value);
+                        wait(&mut state.senders, waiter, cx);
+                        return Poll::Pending;
+                    }
+                }
+            };
+            if (*waiter).queued {
+                state.senders.remove(waiter);
+            }
+            this.done = true;
+            if result.is_ok() {
+                channel.sent(state);
+            }
+            Poll::Ready(result)
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for SendValue<'_, T, N> {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        let mut state = self.channel.lock();
+        let waiter = self.waiter.get();
+        // SAFETY: the lock is held.
+        unsafe {
+            if (*waiter).queued {
+                state.senders.remove(waiter);
+            } else if (*waiter).notified {
+                // The room it was woken for goes to the next sender instead.
+                self.channel.received(state);
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for SendValue<'_, T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendValue").finish_non_exhaustive()
+    }
+}
+
+/// Future returned by [`ArrayChannel::recv`].
+pub struct RecvValue<'a, T, const N: usize> {
+    channel: &'a ArrayChannel<T, N>,
+    waiter: UnsafeCell<Waiter>,
+    // Completed, so no longer linked or owed a wakeup.
+    done: bool,
+    _pinned: PhantomPinned,
+}
+
+// SAFETY: the waiter is only touched with the channel's lock held.
+unsafe impl<T: Send, const N: usize> Send for RecvValue<'_, T, N> {}
+
+impl<T, const N: usize> Future for RecvValue<'_, T, N> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        ready!(crate::coop::poll_proceed(cx));
+        // SAFETY: nothing is moved out of `self`.
+        let this = unsafe { self.get_unchecked_mut() };
+        let channel = this.channel;
+        let waiter = this.waiter.get();
+        let mut state = channel.lock();
+        // SAFETY: the lock is held, and the future is pinned until it unlinks in `drop`.
+        unsafe {
+            (*waiter).notified = false;
+            let value = state.pop();
+            if value.is_none() && !state.closed {
+                wait(&mut state.receivers, waiter, cx);
+                return Poll::Pending;
+            }
+            if (*waiter).queued {
+                state.receivers.remove(waiter);
+            }
+            this.done = true;
+            match value {
+                Some(value) => {
+                    channel.received(state);
+                    Poll::Ready(Ok(value))
+                }
+                None => Poll::Ready(Err(RecvError(()))),
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for RecvValue<'_, T, N> {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        let mut state = self.channel.lock();
+        let waiter = self.waiter.get();
+        // SAFETY: the lock is held.
+        unsafe {
+            if (*waiter).queued {
+                state.receivers.remove(waiter);
+            } else if (*waiter).notified {
+                // The value it was woken for goes to the next receiver instead.
+                self.channel.sent(state);
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for RecvValue<'_, T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecvValue").finish_non_exhaustive()
+    }
+}
+
+/// Error returned by [`ArrayChannel::send`] and [`ArrayChannel::send_blocking`] when the channel
+/// is closed, holding the unsent value.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("array channel closed")
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+/// Error returned by [`ArrayChannel::try_send`], holding the unsent value.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// The channel is full.
+    Full(T),
+    /// The channel is closed.
+    Closed(T),
+}
+
+impl<T> TrySendError<T> {
+    /// Get back the value that wasn't sent.
+    pub fn into_inner(self) -> T {
+        match self {
+            TrySendError::Full(value) | TrySendError::Closed(value) => value,
+        }
+    }
+}
+
+impl<T> fmt::Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.debug_tuple("Full").finish_non_exhaustive(),
+            TrySendError::Closed(_) => f.debug_tuple("Closed").finish_non_exhaustive(),
+        }
+    }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("array channel full"),
+            TrySendError::Closed(_) => f.write_str("array channel closed"),
+        }
+    }
+}
+
+impl<T> Error for TrySendError<T> {}
+
+/// Error returned by [`ArrayChannel::recv`] and [`ArrayChannel::recv_blocking`] once the channel
+/// is closed and empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError(());
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("array channel closed")
+    }
+}
+
+impl Error for RecvError {}
+
+/// Error returned by [`ArrayChannel::try_recv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// No value is waiting.
+    Empty,
+    /// The channel is closed and no values are left.
+    Closed,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => f.write_str("array channel empty"),
+            TryRecvError::Closed => f.write_str("array channel closed"),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
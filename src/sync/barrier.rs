@@ -0,0 +1,118 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Mutex,
+    task::{Context, Poll, Waker},
+};
+
+struct BarrierState {
+    arrived: usize,
+    generation: usize,
+    wakers: Vec<Waker>,
+}
+
+/// A barrier that lets a fixed number of participants rendezvous, whether they are threads
+/// blocking with [`Barrier::wait_blocking`] or futures awaiting [`Barrier::wait`].
+///
+/// # Example
+///
+/// ```
+/// use std::{sync::Arc, thread};
+/// use pollster::sync::Barrier;
+///
+/// let barrier = Arc::new(Barrier::new(2));
+///
+/// let thread = thread::spawn({
+///     let barrier = Arc::clone(&barrier);
+///     move || barrier.wait_blocking()
+/// });
+/// let from_future = pollster::block_on(barrier.wait());
+/// let from_thread = thread.join().unwrap();
+///
+/// assert!(from_future.is_leader() != from_thread.is_leader());
+/// ```
+pub struct Barrier {
+    n: usize,
+    state: Mutex<BarrierState>,
+}
+
+impl Barrier {
+    /// Create a barrier that releases its waiters once `n` of them have arrived.
+    ///
+    /// A barrier of size 0 behaves like a barrier of size 1.
+    pub fn new(n: usize) -> Self {
+        Self {
+            n: n.max(1),
+            state: Mutex::new(BarrierState {
+                arrived: 0,
+                generation: 0,
+                wakers: Vec::new(),
+            }),
+        }
+    }
+
+    /// Wait asynchronously until all participants have arrived.
+    ///
+    /// A participant is counted as soon as the returned future is first polled. Dropping the
+    /// future afterwards does not withdraw it.
+    pub fn wait(&self) -> BarrierWait<'_> {
+        BarrierWait {
+            barrier: self,
+            arrival: None,
+        }
+    }
+
+    /// Block the current thread until all participants have arrived.
+    pub fn wait_blocking(&self) -> BarrierWaitResult {
+        crate::block_on(self.wait())
+    }
+}
+
+/// Future returned by [`Barrier::wait`].
+pub struct BarrierWait<'a> {
+    barrier: &'a Barrier,
+    // Generation we arrived in and the index of our waker in that generation.
+    arrival: Option<(usize, usize)>,
+}
+
+impl Future for BarrierWait<'_> {
+    type Output = BarrierWaitResult;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.barrier.state.lock().unwrap();
+        match self.arrival {
+            Some((generation, _)) if generation != state.generation => Poll::Ready(BarrierWaitResult(false)),
+            Some((_, index)) => {
+                if !state.wakers[index].will_wake(cx.waker()) {
+                    state.wakers[index] = cx.waker().clone();
+                }
+                Poll::Pending
+            }
+            None if state.arrived + 1 == self.barrier.n => {
+                state.arrived = 0;
+                state.generation = state.generation.wrapping_add(1);
+                state.wakers.drain(..).for_each(Waker::wake);
+                Poll::Ready(BarrierWaitResult(true))
+            }
+            None => {
+                state.arrived += 1;
+                state.wakers.push(cx.waker().clone());
+                let arrival = (state.generation, state.wakers.len() - 1);
+                drop(state);
+                self.arrival = Some(arrival);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Returned once a [`Barrier`] releases its waiters.
+#[derive(Debug, Clone, Copy)]
+pub struct BarrierWaitResult(bool);
+
+impl BarrierWaitResult {
+    /// Whether this waiter was the last to arrive. Exactly one waiter per generation is the leader.
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
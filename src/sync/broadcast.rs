@@ -0,0 +1,321 @@
+//! A multi-producer, multi-consumer channel where every receiver sees every message.
+//!
+//! The channel keeps the most recent `capacity` messages. A receiver that falls further behind
+//! than that skips ahead to the oldest retained message, and its next receive reports how many it
+//! missed with [`RecvError::Lagged`].
+//!
+//! # Example
+//!
+//! ```
+//! use pollster::sync::broadcast;
+//!
+//! let (tx, mut rx1) = broadcast::channel(16);
+//! let mut rx2 = tx.subscribe();
+//!
+//! let thread = std::thread::spawn(move || rx2.recv_blocking());
+//! tx.send("reload").unwrap();
+//!
+//! assert_eq!(pollster::block_on(rx1.recv()), Ok("reload"));
+//! assert_eq!(thread.join().unwrap(), Ok("reload"));
+//! ```
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    error::Error,
+    fmt,
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll, Waker},
+};
+
+struct State<T> {
+    buffer: VecDeque<T>,
+    // Sequence number of the oldest message in `buffer`.
+    head: u64,
+    senders: usize,
+    receivers: usize,
+    next_id: u64,
+    // Wakers of pending receives, by receiver id.
+    wakers: BTreeMap<u64, Waker>,
+}
+
+impl<T> State<T> {
+    fn tail(&self) -> u64 {
+        self.head + self.buffer.len() as u64
+    }
+
+    // Take the message a receiver at position `next` should see next.
+    fn take(&self, next: &mut u64) -> Result<T, TryRecvError>
+    where
+        T: Clone,
+    {
+        if *next < self.head {
+            let missed = self.head - *next;
+            *next = self.head;
+            return Err(TryRecvError::Lagged(missed));
+        }
+        match self.buffer.get((*next - self.head) as usize) {
+            Some(value) => {
+                *next += 1;
+                Ok(value.clone())
+            }
+            None if self.senders == 0 => Err(TryRecvError::Closed),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+}
+
+struct Shared<T> {
+    capacity: usize,
+    state: Mutex<State<T>>,
+}
+
+/// Create a broadcast channel retaining up to `capacity` messages for slow receivers.
+///
+/// # Panics
+///
+/// Panics if `capacity` is 0.
+pub fn channel<T: Clone>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "broadcast channel capacity must be non-zero");
+    let shared = Arc::new(Shared {
+        capacity,
+        state: Mutex::new(State {
+            buffer: VecDeque::with_capacity(capacity),
+            head: 0,
+            senders: 1,
+            receivers: 1,
+            next_id: 1,
+            wakers: BTreeMap::new(),
+        }),
+    });
+    let rx = Receiver {
+        shared: Arc::clone(&shared),
+        id: 0,
+        next: 0,
+    };
+    (Sender { shared }, rx)
+}
+
+/// The sending half of a broadcast channel. It can be cloned to send from several places.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Send a message to every current receiver, returning how many there are.
+    ///
+    /// Never blocks. If the buffer is full, the oldest message is discarded for everyone. Fails,
+    /// handing the message back, if there are no receivers.
+    pub fn send(&self, value: T) -> Result<usize, SendError<T>> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.receivers == 0 {
+            return Err(SendError(value));
+        }
+        if state.buffer.len() == self.shared.capacity {
+            state.buffer.pop_front();
+            state.head += 1;
+        }
+        state.buffer.push_back(value);
+        let receivers = state.receivers;
+        let wakers = mem::take(&mut state.wakers);
+        drop(state);
+        wakers.into_values().for_each(Waker::wake);
+        Ok(receivers)
+    }
+
+    /// Create a new receiver that sees every message sent from now on.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let mut state = self.shared.state.lock().unwrap();
+        state.receivers += 1;
+        let id = state.next_id;
+        state.next_id += 1;
+        Receiver {
+            shared: Arc::clone(&self.shared),
+            id,
+            next: state.tail(),
+        }
+    }
+
+    /// The number of receivers currently alive.
+    pub fn receiver_count(&self) -> usize {
+        self.shared.state.lock().unwrap().receivers
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.state.lock().unwrap().senders += 1;
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.senders -= 1;
+        if state.senders == 0 {
+            let wakers = mem::take(&mut state.wakers);
+            drop(state);
+            wakers.into_values().for_each(Waker::wake);
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+/// The receiving half of a broadcast channel.
+///
+/// Cloning a receiver creates another one at the same position in the channel.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    id: u64,
+    // Sequence number of the next message to receive.
+    next: u64,
+}
+
+impl<T: Clone> Receiver<T> {
+    /// Receive the next message asynchronously.
+    ///
+    /// Fails with [`RecvError::Closed`] once every sender is gone and all retained messages have
+    /// been received.
+    pub fn recv(&mut self) -> Recv<'_, T> {
+        Recv { receiver: self }
+    }
+
+    /// Block the current thread until the next message arrives.
+    pub fn recv_blocking(&mut self) -> Result<T, RecvError> {
+        crate::block_on(self.recv())
+    }
+
+    /// Receive the next message if one is available, without waiting.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.shared.state.lock().unwrap().take(&mut self.next)
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        let mut state = self.shared.state.lock().unwrap();
+        state.receivers += 1;
+        let id = state.next_id;
+        state.next_id += 1;
+        Self {
+            shared: Arc::clone(&self.shared),
+            id,
+            next: self.next,
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.receivers -= 1;
+        state.wakers.remove(&self.id);
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+/// Future returned by [`Receiver::recv`].
+pub struct Recv<'a, T> {
+    receiver: &'a mut Receiver<T>,
+}
+
+impl<T: Clone> Future for Recv<'_, T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        ready!(crate::coop::poll_proceed(cx));
+        let receiver = &mut *self.receiver;
+        let mut state = receiver.shared.state.lock().unwrap();
+        match state.take(&mut receiver.next) {
+            Ok(value) => Poll::Ready(Ok(value)),
+            Err(TryRecvError::Lagged(missed)) => Poll::Ready(Err(RecvError::Lagged(missed))),
+            Err(TryRecvError::Closed) => Poll::Ready(Err(RecvError::Closed)),
+            Err(TryRecvError::Empty) => {
+                match state.wakers.get_mut(&receiver.id) {
+                    Some(waker) if waker.will_wake(cx.waker()) => {}
+                    _ => {
+                        state.wakers.insert(receiver.id, cx.waker().clone());
+                    }
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Error returned by [`Sender::send`] when there are no receivers, holding the unsent message.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("broadcast channel has no receivers")
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+/// Error returned by [`Receiver::recv`] and [`Receiver::recv_blocking`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvError {
+    /// Every sender is gone and no messages are left.
+    Closed,
+    /// The receiver fell behind and this many messages were discarded before it saw them. The
+    /// next receive continues with the oldest retained message.
+    Lagged(u64),
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvError::Closed => f.write_str("broadcast channel closed"),
+            RecvError::Lagged(missed) => write!(f, "broadcast receiver lagged behind by {} messages", missed),
+        }
+    }
+}
+
+impl Error for RecvError {}
+
+/// Error returned by [`Receiver::try_recv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// No message is available yet.
+    Empty,
+    /// Every sender is gone and no messages are left.
+    Closed,
+    /// The receiver fell behind and this many messages were discarded before it saw them.
+    Lagged(u64),
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => f.write_str("broadcast channel empty"),
+            TryRecvError::Closed => f.write_str("broadcast channel closed"),
+            TryRecvError::Lagged(missed) => write!(f, "broadcast receiver lagged behind by {} messages", missed),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
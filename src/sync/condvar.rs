@@ -0,0 +1,213 @@
+use super::mutex::{Lock, Mutex, MutexGuard};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync,
+    task::{ready, Context, Poll, Waker},
+};
+
+struct State {
+    next_id: u64,
+    // Wakers of pending waits, by id, so the longest-waiting one comes first.
+    waiters: BTreeMap<u64, Waker>,
+}
+
+/// A condition variable that can be waited on by threads blocking with
+/// [`Condvar::wait_blocking`] or by futures awaiting [`Condvar::wait`], paired with this module's
+/// [`Mutex`].
+///
+/// Both kinds of waiter can share one condition, so code written against
+/// [`std::sync::Condvar`] can move to futures a piece at a time. As with the std version, a wait
+/// can return without a notification, so check the condition in a loop.
+///
+/// # Example
+///
+/// ```
+/// use std::{sync::Arc, thread};
+/// use pollster::sync::{Condvar, Mutex};
+///
+/// let pair = Arc::new((Mutex::new(false), Condvar::new()));
+/// let waiter = thread::spawn({
+///     let pair = Arc::clone(&pair);
+///     move || {
+///         let (ready, cvar) = &*pair;
+///         let mut ready = ready.lock_blocking();
+///         while !*ready {
+///             ready = cvar.wait_blocking(ready);
+///         }
+///     }
+/// });
+///
+/// pollster::block_on(async {
+///     let (ready, cvar) = &*pair;
+///     *ready.lock().await = true;
+///     cvar.notify_all();
+/// });
+/// waiter.join().unwrap();
+/// ```
+pub struct Condvar {
+    state: sync::Mutex<State>,
+}
+
+impl Condvar {
+    /// Create a condition variable with no waiters.
+    pub fn new() -> Self {
+        Self {
+            state: sync::Mutex::new(State {
+                next_id: 0,
+                waiters: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Unlock the mutex behind `guard` and wait asynchronously for a notification, resolving to
+    /// the guard once the mutex is locked again.
+    ///
+    /// The wait is registered when the future is first polled, before the mutex is unlocked, so a
+    /// notification sent under the lock afterwards is never missed. Dropping the future after it
+    /// has been notified passes the notification on to another waiter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{sync::Arc, thread};
+    /// use pollster::sync::{Condvar, Mutex};
+    ///
+    /// let queue = Arc::new((Mutex::new(Vec::new()), Condvar::new()));
+    /// thread::spawn({
+    ///     let queue = Arc::clone(&queue);
+    ///     move || {
+    ///         queue.0.lock_blocking().push("job");
+    ///         queue.1.notify_one();
+    ///     }
+    /// });
+    ///
+    /// let job = pollster::block_on(async {
+    ///     let (jobs, cvar) = &*queue;
+    ///     let mut jobs = jobs.lock().await;
+    ///     loop {
+    ///         match jobs.pop() {
+    ///             Some(job) => break job,
+    ///             None => jobs = cvar.wait(jobs).await,
+    ///         }
+    ///     }
+    /// });
+    /// assert_eq!(job, "job");
+    /// ```
+    pub fn wait<'a, T: ?Sized>(&self, guard: MutexGuard<'a, T>) -> Wait<'_, 'a, T> {
+        Wait {
+            condvar: self,
+            mutex: guard.mutex(),
+            guard: Some(guard),
+            id: None,
+            lock: None,
+        }
+    }
+
+    /// Unlock the mutex behind `guard` and block the current thread until notified, returning the
+    /// guard once the mutex is locked again.
+    #[track_caller]
+    pub fn wait_blocking<'a, T: ?Sized>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        crate::block_on(self.wait(guard))
+    }
+
+    /// Wake the longest-waiting waiter, if there is one.
+    pub fn notify_one(&self) {
+        let waker = self.state.lock().unwrap().waiters.pop_first();
+        if let Some((_, waker)) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Wake every waiter.
+    pub fn notify_all(&self) {
+        let waiters = std::mem::take(&mut self.state.lock().unwrap().waiters);
+        waiters.into_values().for_each(Waker::wake);
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Condvar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Condvar").finish_non_exhaustive()
+    }
+}
+
+/// Future returned by [`Condvar::wait`].
+pub struct Wait<'c, 'a, T: ?Sized> {
+    condvar: &'c Condvar,
+    mutex: &'a Mutex<T>,
+    // Held until the first poll.
+    guard: Option<MutexGuard<'a, T>>,
+    // Set while waiting for a notification.
+    id: Option<u64>,
+    // Set once notified, to lock the mutex again.
+    lock: Option<Lock<'a, T>>,
+}
+
+impl<'a, T: ?Sized> Future for Wait<'_, 'a, T> {
+    type Output = MutexGuard<'a, T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<MutexGuard<'a, T>> {
+        let this = self.get_mut();
+        if let Some(guard) = this.guard.take() {
+            let mut state = this.condvar.state.lock().unwrap();
+            state.next_id += 1;
+            let id = state.next_id;
+            state.waiters.insert(id, cx.waker().clone());
+            drop(state);
+            this.id = Some(id);
+            drop(guard);
+            return Poll::Pending;
+        }
+
+        if let Some(id) = this.id {
+            let mut state = this.condvar.state.lock().unwrap();
+            if let Some(waker) = state.waiters.get_mut(&id) {
+                if !waker.will_wake(cx.waker()) {
+                    *waker = cx.waker().clone();
+                }
+                return Poll::Pending;
+            }
+            drop(state);
+            this.id = None;
+            this.lock = Some(this.mutex.lock());
+        }
+
+        let guard = match &mut this.lock {
+            Some(lock) => ready!(Pin::new(lock).poll(cx)),
+            None => panic!("`Wait` polled after completion"),
+        };
+        this.lock = None;
+        Poll::Ready(guard)
+    }
+}
+
+impl<T: ?Sized> Drop for Wait<'_, '_, T> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            let mut state = self.condvar.state.lock().unwrap();
+            // Notified but gone before noticing, so pass the notification on.
+            if state.waiters.remove(&id).is_none() {
+                let waker = state.waiters.pop_first();
+                drop(state);
+                if let Some((_, waker)) = waker {
+                    waker.wake();
+                }
+            }
+        }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Wait<'_, '_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Wait").finish_non_exhaustive()
+    }
+}
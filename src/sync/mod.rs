@@ -0,0 +1,14 @@
+//! Synchronization primitives usable from both plain threads and futures.
+
+pub mod array_channel;
+mod barrier;
+pub mod broadcast;
+mod condvar;
+mod mutex;
+mod once_cell;
+pub mod watch;
+
+pub use barrier::{Barrier, BarrierWait, BarrierWaitResult};
+pub use condvar::{Condvar, Wait};
+pub use mutex::{Lock, Mutex, MutexGuard};
+pub use once_cell::OnceCell;
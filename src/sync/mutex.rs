@@ -0,0 +1,240 @@
+use std::{
+    cell::UnsafeCell,
+    collections::BTreeMap,
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    sync,
+    task::{Context, Poll, Waker},
+};
+
+struct State {
+    locked: bool,
+    next_id: u64,
+    // Wakers of pending `lock` calls, by id, so the longest-waiting one comes first.
+    waiters: BTreeMap<u64, Waker>,
+}
+
+impl State {
+    fn wake_next(&mut self) -> Option<Waker> {
+        self.waiters.pop_first().map(|(_, waker)| waker)
+    }
+}
+
+/// A mutual exclusion lock that can be acquired by threads blocking with
+/// [`Mutex::lock_blocking`] or by futures awaiting [`Mutex::lock`].
+///
+/// Unlike [`std::sync::Mutex`], a guard can be held across an `.await`, and the mutex isn't
+/// poisoned by a panic while it is held; the lock is simply released. It pairs with
+/// [`Condvar`](super::Condvar).
+///
+/// # Example
+///
+/// ```
+/// use std::{sync::Arc, thread};
+/// use pollster::sync::Mutex;
+///
+/// let count = Arc::new(Mutex::new(0));
+/// let thread = thread::spawn({
+///     let count = Arc::clone(&count);
+///     move || *count.lock_blocking() += 1
+/// });
+/// pollster::block_on(async { *count.lock().await += 1 });
+/// thread.join().unwrap();
+///
+/// assert_eq!(*count.lock_blocking(), 2);
+/// ```
+pub struct Mutex<T: ?Sized> {
+    state: sync::Mutex<State>,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: the value is only reached through a guard, and only one guard exists at a time.
+unsafe impl<T: ?Sized + Send> Send for Mutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for Mutex<T> {}
+
+impl<T> Mutex<T> {
+    /// Create an unlocked mutex holding `value`.
+    pub fn new(value: T) -> Self {
+        Self {
+            state: sync::Mutex::new(State {
+                locked: false,
+                next_id: 0,
+                waiters: BTreeMap::new(),
+            }),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consume the mutex, returning its value.
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    /// Wait asynchronously until the lock is acquired.
+    ///
+    /// Waiters are woken in the order they started waiting, but a caller that finds the mutex
+    /// unlocked takes it straight away.
+    pub fn lock(&self) -> Lock<'_, T> {
+        Lock { mutex: self, id: None }
+    }
+
+    /// Block the current thread until the lock is acquired.
+    #[track_caller]
+    pub fn lock_blocking(&self) -> MutexGuard<'_, T> {
+        crate::block_on(self.lock())
+    }
+
+    /// Acquire the lock if it is free, without waiting.
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        let mut state = self.state.lock().unwrap();
+        if state.locked {
+            return None;
+        }
+        state.locked = true;
+        Some(MutexGuard::new(self))
+    }
+
+    /// Borrow the value mutably, which needs no locking since the mutex is borrowed exclusively.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+
+    fn unlock(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.locked = false;
+        let waker = state.wake_next();
+        drop(state);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T: Default> Default for Mutex<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Mutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mutex")
+            .field("locked", &self.state.lock().unwrap().locked)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Future returned by [`Mutex::lock`].
+pub struct Lock<'a, T: ?Sized> {
+    mutex: &'a Mutex<T>,
+    // Set while waiting for the lock.
+    id: Option<u64>,
+}
+
+impl<'a, T: ?Sized> Future for Lock<'a, T> {
+    type Output = MutexGuard<'a, T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<MutexGuard<'a, T>> {
+        let mut state = self.mutex.state.lock().unwrap();
+        if !state.locked {
+            state.locked = true;
+            if let Some(id) = self.id.take() {
+                state.waiters.remove(&id);
+            }
+            return Poll::Ready(MutexGuard::new(self.mutex));
+        }
+
+        let id = match self.id {
+            Some(id) => id,
+            None => {
+                state.next_id += 1;
+                state.next_id
+            }
+        };
+        // Keeps the original id after a wake that lost the race, so the place in line is kept.
+        match state.waiters.get_mut(&id) {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            _ => {
+                state.waiters.insert(id, cx.waker().clone());
+            }
+        }
+        drop(state);
+        self.id = Some(id);
+        Poll::Pending
+    }
+}
+
+impl<T: ?Sized> Drop for Lock<'_, T> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            let mut state = self.mutex.state.lock().unwrap();
+            // Woken for an unlock but gone before taking the lock, so pass the wake on.
+            if state.waiters.remove(&id).is_none() && !state.locked {
+                let waker = state.wake_next();
+                drop(state);
+                if let Some(waker) = waker {
+                    waker.wake();
+                }
+            }
+        }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Lock<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lock").finish_non_exhaustive()
+    }
+}
+
+/// Access to the value of a locked [`Mutex`], which is unlocked when the guard is dropped.
+pub struct MutexGuard<'a, T: ?Sized> {
+    mutex: &'a Mutex<T>,
+    // Shares the value like a `&mut T` would, for the auto traits.
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T: ?Sized> MutexGuard<'a, T> {
+    fn new(mutex: &'a Mutex<T>) -> Self {
+        Self {
+            mutex,
+            _marker: PhantomData,
+        }
+    }
+
+    pub(super) fn mutex(&self) -> &'a Mutex<T> {
+        self.mutex
+    }
+}
+
+impl<T: ?Sized> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the guard holds the lock.
+        unsafe { &*self.mutex.value.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: the guard holds the lock.
+        unsafe { &mut *self.mutex.value.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.unlock();
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for MutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Mutex, OnceLock},
+    task::{Context, Poll, Waker},
+};
+
+struct InitState {
+    running: bool,
+    wakers: Vec<Waker>,
+}
+
+/// A cell that is initialized at most once by an asynchronous initializer, which may be driven
+/// either by a future awaiting [`OnceCell::get_or_init`] or by a thread calling
+/// [`OnceCell::get_or_init_blocking`].
+///
+/// Only one initializer runs at a time. If it is dropped before completing, the next waiter in
+/// line gets to run its own initializer instead.
+///
+/// # Example
+///
+/// ```
+/// use pollster::sync::OnceCell;
+///
+/// static DEVICE: OnceCell<String> = OnceCell::new();
+///
+/// let device = DEVICE.get_or_init_blocking(|| async { "gpu0".to_string() });
+/// assert_eq!(device, "gpu0");
+///
+/// let again = pollster::block_on(DEVICE.get_or_init(|| async { unreachable!() }));
+/// assert_eq!(again, "gpu0");
+/// ```
+pub struct OnceCell<T> {
+    value: OnceLock<T>,
+    state: Mutex<InitState>,
+}
+
+impl<T> OnceCell<T> {
+    /// Create an empty cell.
+    pub const fn new() -> Self {
+        Self {
+            value: OnceLock::new(),
+            state: Mutex::new(InitState {
+                running: false,
+                wakers: Vec::new(),
+            }),
+        }
+    }
+
+    /// Get the value, if the cell has been initialized.
+    pub fn get(&self) -> Option<&T> {
+        self.value.get()
+    }
+
+    /// Initialize the cell with `value`, returning it back if the cell was already initialized.
+    pub fn set(&self, value: T) -> Result<(), T> {
+        self.value.set(value)
+    }
+
+    /// Get the value, running `init` to produce it if the cell is empty.
+    pub async fn get_or_init<F, Fut>(&self, init: F) -> &T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        if let Some(value) = self.get() {
+            return value;
+        }
+        if let Some(_permit) = (Acquire { cell: self }).await {
+            let _ = self.value.set(init().await);
+        }
+        self.get().expect("OnceCell initializer finished without a value")
+    }
+
+    /// Block the current thread until the value is available, running `init` to produce it if
+    /// the cell is empty.
+    pub fn get_or_init_blocking<F, Fut>(&self, init: F) -> &T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        match self.get() {
+            Some(value) => value,
+            None => crate::block_on(self.get_or_init(init)),
+        }
+    }
+
+    /// Consume the cell, returning its value if it was initialized.
+    pub fn into_inner(self) -> Option<T> {
+        self.value.into_inner()
+    }
+}
+
+impl<T> Default for OnceCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Resolves to `Some` when the caller has been elected to run the initializer, or `None` once the
+// cell holds a value.
+struct Acquire<'a, T> {
+    cell: &'a OnceCell<T>,
+}
+
+impl<'a, T> Future for Acquire<'a, T> {
+    type Output = Option<Permit<'a, T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.cell.state.lock().unwrap();
+        if self.cell.value.get().is_some() {
+            Poll::Ready(None)
+        } else if !state.running {
+            state.running = true;
+            Poll::Ready(Some(Permit { cell: self.cell }))
+        } else {
+            if !state.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                state.wakers.push(cx.waker().clone());
+            }
+            Poll::Pending
+        }
+    }
+}
+
+struct Permit<'a, T> {
+    cell: &'a OnceCell<T>,
+}
+
+impl<T> Drop for Permit<'_, T> {
+    fn drop(&mut self) {
+        let mut state = self.cell.state.lock().unwrap();
+        state.running = false;
+        state.wakers.drain(..).for_each(Waker::wake);
+    }
+}
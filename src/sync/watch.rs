@@ -0,0 +1,352 @@
+//! A single-producer, multi-consumer channel that only keeps the latest value.
+//!
+//! Receivers don't see every value sent, only the most recent one when they look. That suits
+//! state like configuration, where a consumer that falls behind should skip straight to the
+//! current value.
+//!
+//! # Example
+//!
+//! ```
+//! use pollster::sync::watch;
+//!
+//! let (tx, mut rx) = watch::channel(1);
+//! let worker = std::thread::spawn(move || *rx.wait_for_blocking(|level| *level >= 3).unwrap());
+//!
+//! tx.send(2).unwrap();
+//! tx.send(3).unwrap();
+//! assert_eq!(worker.join().unwrap(), 3);
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt,
+    future::Future,
+    mem,
+    ops::Deref,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{ready, Context, Poll, Waker},
+};
+
+struct State<T> {
+    value: T,
+    // Bumped on every send.
+    version: u64,
+    closed: bool,
+    receivers: usize,
+    next_id: u64,
+    // Wakers of pending `changed` calls, by receiver id.
+    wakers: BTreeMap<u64, Waker>,
+}
+
+impl<T> State<T> {
+    fn wake_all(&mut self) -> BTreeMap<u64, Waker> {
+        mem::take(&mut self.wakers)
+    }
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+}
+
+/// Create a watch channel holding `init` as its first value.
+///
+/// Receivers consider the initial value already seen.
+pub fn channel<T>(init: T) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            value: init,
+            version: 0,
+            closed: false,
+            receivers: 1,
+            next_id: 1,
+            wakers: BTreeMap::new(),
+        }),
+    });
+    let rx = Receiver {
+        shared: Arc::clone(&shared),
+        id: 0,
+        seen: 0,
+    };
+    (Sender { shared }, rx)
+}
+
+/// The sending half of a watch channel.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Replace the value and notify every receiver.
+    ///
+    /// Never blocks. Fails, handing the value back, if there are no receivers.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.receivers == 0 {
+            return Err(SendError(value));
+        }
+        state.value = value;
+        state.version += 1;
+        let wakers = state.wake_all();
+        drop(state);
+        wakers.into_values().for_each(Waker::wake);
+        Ok(())
+    }
+
+    /// Modify the value in place and notify every receiver, even if there are none.
+    pub fn send_modify(&self, modify: impl FnOnce(&mut T)) {
+        let mut state = self.shared.state.lock().unwrap();
+        modify(&mut state.value);
+        state.version += 1;
+        let wakers = state.wake_all();
+        drop(state);
+        wakers.into_values().for_each(Waker::wake);
+    }
+
+    /// Borrow the current value.
+    ///
+    /// The channel is locked while the returned reference is alive, so keep it short-lived.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref {
+            guard: self.shared.state.lock().unwrap(),
+        }
+    }
+
+    /// Create a new receiver, which considers the current value already seen.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let mut state = self.shared.state.lock().unwrap();
+        state.receivers += 1;
+        let id = state.next_id;
+        state.next_id += 1;
+        Receiver {
+            shared: Arc::clone(&self.shared),
+            id,
+            seen: state.version,
+        }
+    }
+
+    /// The number of receivers currently alive.
+    pub fn receiver_count(&self) -> usize {
+        self.shared.state.lock().unwrap().receivers
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.closed = true;
+        let wakers = state.wake_all();
+        drop(state);
+        wakers.into_values().for_each(Waker::wake);
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+/// The receiving half of a watch channel.
+///
+/// Cloning a receiver creates another one that has seen the same values.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    id: u64,
+    // The version of the value last marked as seen.
+    seen: u64,
+}
+
+impl<T> Receiver<T> {
+    /// Borrow the current value, without marking it as seen.
+    ///
+    /// The channel is locked while the returned reference is alive, so keep it short-lived.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref {
+            guard: self.shared.state.lock().unwrap(),
+        }
+    }
+
+    /// Borrow the current value and mark it as seen.
+    pub fn borrow_and_update(&mut self) -> Ref<'_, T> {
+        let guard = self.shared.state.lock().unwrap();
+        self.seen = guard.version;
+        Ref { guard }
+    }
+
+    /// Whether a value has been sent since the last one marked as seen.
+    ///
+    /// Fails once the sender is gone.
+    pub fn has_changed(&self) -> Result<bool, RecvError> {
+        let state = self.shared.state.lock().unwrap();
+        if state.closed {
+            return Err(RecvError(()));
+        }
+        Ok(state.version != self.seen)
+    }
+
+    /// Wait for a value that hasn't been seen yet, and mark it as seen.
+    ///
+    /// Completes straight away if one was sent since the last one seen. Fails once the sender is
+    /// gone and every value has been seen.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use pollster::sync::watch;
+    ///
+    /// let (tx, mut rx) = watch::channel("v1");
+    /// std::thread::spawn(move || tx.send("v2").unwrap());
+    /// pollster::block_on(async {
+    ///     rx.changed().await.unwrap();
+    ///     assert_eq!(*rx.borrow(), "v2");
+    ///     // The sender is gone now.
+    ///     assert!(rx.changed().await.is_err());
+    /// });
+    /// ```
+    pub fn changed(&mut self) -> Changed<'_, T> {
+        Changed {
+            shared: &self.shared,
+            id: self.id,
+            seen: &mut self.seen,
+        }
+    }
+
+    /// Block the current thread until a value that hasn't been seen yet is sent, like
+    /// [`changed`](Receiver::changed).
+    pub fn changed_blocking(&mut self) -> Result<(), RecvError> {
+        crate::block_on(self.changed())
+    }
+
+    /// Block the current thread until the value satisfies `predicate`, and mark it as seen.
+    ///
+    /// The current value is checked first, then every new value as it arrives; values replaced
+    /// before the receiver got to look at them are never checked. Fails once the sender is gone
+    /// if the final value doesn't satisfy `predicate`.
+    pub fn wait_for_blocking(&mut self, mut predicate: impl FnMut(&T) -> bool) -> Result<Ref<'_, T>, RecvError> {
+        let shared = &*self.shared;
+        loop {
+            let state = shared.state.lock().unwrap();
+            self.seen = state.version;
+            if predicate(&state.value) {
+                return Ok(Ref { guard: state });
+            } else if state.closed {
+                return Err(RecvError(()));
+            }
+            drop(state);
+            crate::block_on(Changed {
+                shared,
+                id: self.id,
+                seen: &mut self.seen,
+            })?;
+        }
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        let mut state = self.shared.state.lock().unwrap();
+        state.receivers += 1;
+        let id = state.next_id;
+        state.next_id += 1;
+        Self {
+            shared: Arc::clone(&self.shared),
+            id,
+            seen: self.seen,
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.receivers -= 1;
+        state.wakers.remove(&self.id);
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+/// A borrowed reference to the value in a watch channel, returned by the `borrow` methods.
+pub struct Ref<'a, T> {
+    guard: MutexGuard<'a, State<T>>,
+}
+
+impl<T> Deref for Ref<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard.value
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Ref<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// Future returned by [`Receiver::changed`].
+pub struct Changed<'a, T> {
+    shared: &'a Shared<T>,
+    id: u64,
+    seen: &'a mut u64,
+}
+
+impl<T> Future for Changed<'_, T> {
+    type Output = Result<(), RecvError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        ready!(crate::coop::poll_proceed(cx));
+        let this = &mut *self;
+        let mut state = this.shared.state.lock().unwrap();
+        if state.version != *this.seen {
+            *this.seen = state.version;
+            return Poll::Ready(Ok(()));
+        } else if state.closed {
+            return Poll::Ready(Err(RecvError(())));
+        }
+        match state.wakers.get_mut(&this.id) {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            _ => {
+                state.wakers.insert(this.id, cx.waker().clone());
+            }
+        }
+        Poll::Pending
+    }
+}
+
+/// Error returned by [`Sender::send`] when there are no receivers, holding the unsent value.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("watch channel has no receivers")
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+/// Error returned by a [`Receiver`] once the sender is gone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError(());
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("watch channel closed")
+    }
+}
+
+impl Error for RecvError {}
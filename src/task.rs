@@ -0,0 +1,176 @@
+//! Values scoped to a future rather than to a thread, and cooperative yielding.
+
+pub use crate::coop::consume_budget;
+
+use std::{
+    cell::RefCell,
+    error::Error,
+    fmt,
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{Context, Poll},
+    thread,
+};
+
+/// Declare one or more task-local keys of type [`LocalKey`].
+///
+/// # Example
+///
+/// ```
+/// pollster::task_local! {
+///     static REQUEST_ID: u64;
+/// }
+///
+/// async fn handle() -> u64 {
+///     REQUEST_ID.get()
+/// }
+///
+/// let id = pollster::block_on(REQUEST_ID.scope(42, handle()));
+/// assert_eq!(id, 42);
+/// ```
+#[macro_export]
+macro_rules! task_local {
+    () => {};
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty; $($rest:tt)*) => {
+        $crate::__task_local_inner!($(#[$attr])* $vis $name, $t);
+        $crate::task_local!($($rest)*);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty) => {
+        $crate::__task_local_inner!($(#[$attr])* $vis $name, $t);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __task_local_inner {
+    ($(#[$attr:meta])* $vis:vis $name:ident, $t:ty) => {
+        $(#[$attr])*
+        $vis static $name: $crate::task::LocalKey<$t> = {
+            ::std::thread_local! {
+                static __POLLSTER_TASK_LOCAL: ::std::cell::RefCell<::std::option::Option<$t>> =
+                    ::std::cell::RefCell::new(::std::option::Option::None);
+            }
+            $crate::task::LocalKey { inner: __POLLSTER_TASK_LOCAL }
+        };
+    };
+}
+
+/// A key for a task-local value, declared with [`task_local!`](crate::task_local).
+///
+/// The value is only visible while a future created by [`LocalKey::scope`] (or a closure run by
+/// [`LocalKey::sync_scope`]) is executing, on whichever thread happens to be polling it.
+pub struct LocalKey<T: 'static> {
+    #[doc(hidden)]
+    pub inner: thread::LocalKey<RefCell<Option<T>>>,
+}
+
+impl<T: 'static> LocalKey<T> {
+    /// Wrap `future` so that this key holds `value` whenever it is polled.
+    pub fn scope<F: Future>(&'static self, value: T, future: F) -> TaskLocalFuture<T, F> {
+        TaskLocalFuture {
+            key: self,
+            slot: Some(value),
+            future,
+        }
+    }
+
+    /// Run `f` with this key holding `value`.
+    pub fn sync_scope<F: FnOnce() -> R, R>(&'static self, value: T, f: F) -> R {
+        let mut slot = Some(value);
+        self.enter(&mut slot, f)
+    }
+
+    /// Access the current value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a scope for this key.
+    pub fn with<F: FnOnce(&T) -> R, R>(&'static self, f: F) -> R {
+        self.try_with(f)
+            .expect("cannot access a task-local value outside of its scope")
+    }
+
+    /// Access the current value, or return an error if called outside of a scope for this key.
+    pub fn try_with<F: FnOnce(&T) -> R, R>(&'static self, f: F) -> Result<R, AccessError> {
+        self.inner
+            .try_with(|cell| cell.borrow().as_ref().map(f))
+            .ok()
+            .flatten()
+            .ok_or(AccessError(()))
+    }
+
+    /// Get a copy of the current value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a scope for this key.
+    pub fn get(&'static self) -> T
+    where
+        T: Clone,
+    {
+        self.with(T::clone)
+    }
+
+    // Swap `slot` into the thread-local for the duration of `f`, restoring it afterwards even if
+    // `f` panics.
+    fn enter<F: FnOnce() -> R, R>(&'static self, slot: &mut Option<T>, f: F) -> R {
+        struct Guard<'a, T: 'static> {
+            key: &'static thread::LocalKey<RefCell<Option<T>>>,
+            slot: &'a mut Option<T>,
+        }
+
+        impl<T: 'static> Drop for Guard<'_, T> {
+            fn drop(&mut self) {
+                self.key.with(|cell| mem::swap(&mut *cell.borrow_mut(), self.slot));
+            }
+        }
+
+        self.inner.with(|cell| {
+            let mut current = cell
+                .try_borrow_mut()
+                .expect("cannot enter a task-local scope while the value is borrowed");
+            mem::swap(&mut *current, slot);
+        });
+        let _guard = Guard { key: &self.inner, slot };
+        f()
+    }
+}
+
+impl<T: 'static> fmt::Debug for LocalKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("LocalKey { .. }")
+    }
+}
+
+/// Future returned by [`LocalKey::scope`].
+pub struct TaskLocalFuture<T: 'static, F> {
+    key: &'static LocalKey<T>,
+    slot: Option<T>,
+    future: F,
+}
+
+impl<T: 'static, F: Future> Future for TaskLocalFuture<T, F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `future` is never moved out of `self`; `slot` is not structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        // SAFETY: `TaskLocalFuture` has no `Drop` impl and is only `Unpin` when the `future` it
+        // wraps is, so `future` stays pinned for as long as `self` is.
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+        this.key.enter(&mut this.slot, || future.poll(cx))
+    }
+}
+
+/// Returned by [`LocalKey::try_with`] when no value is in scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessError(());
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("task-local value accessed outside of its scope")
+    }
+}
+
+impl Error for AccessError {}
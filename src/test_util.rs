@@ -0,0 +1,135 @@
+//! Utilities for testing hand-written [`Future`](std::future::Future) implementations.
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{RawWaker, RawWakerVTable, Waker},
+};
+
+#[derive(Default)]
+struct Counts {
+    created: AtomicUsize,
+    clones: AtomicUsize,
+    drops: AtomicUsize,
+    wakes: AtomicUsize,
+    wakes_by_ref: AtomicUsize,
+}
+
+/// Hands out instrumented wakers and counts what is done with them, to check a future's waker
+/// discipline.
+///
+/// All wakers created by the same probe share one set of counters.
+///
+/// # Example
+///
+/// ```
+/// use std::{future::Future, pin::Pin, task::{Context, Poll}};
+/// use pollster::test_util::WakerProbe;
+///
+/// struct YieldOnce(bool);
+///
+/// impl Future for YieldOnce {
+///     type Output = ();
+///
+///     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+///         if self.0 {
+///             return Poll::Ready(());
+///         }
+///         self.0 = true;
+///         cx.waker().wake_by_ref();
+///         Poll::Pending
+///     }
+/// }
+///
+/// let probe = WakerProbe::new();
+/// let waker = probe.waker();
+/// let mut fut = YieldOnce(false);
+/// assert!(Pin::new(&mut fut).poll(&mut Context::from_waker(&waker)).is_pending());
+/// assert_eq!((probe.wakes_by_ref(), probe.clones()), (1, 0));
+///
+/// drop(waker);
+/// assert_eq!(probe.live(), 0);
+/// ```
+#[derive(Clone, Default)]
+pub struct WakerProbe {
+    counts: Arc<Counts>,
+}
+
+impl WakerProbe {
+    /// Create a probe with all counters at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new instrumented waker.
+    pub fn waker(&self) -> Waker {
+        self.counts.created.fetch_add(1, Ordering::Relaxed);
+        let ptr = Arc::into_raw(Arc::clone(&self.counts)).cast::<()>();
+        unsafe { Waker::from_raw(RawWaker::new(ptr, &VTABLE)) }
+    }
+
+    /// How many times a waker from this probe was cloned.
+    pub fn clones(&self) -> usize {
+        self.counts.clones.load(Ordering::Relaxed)
+    }
+
+    /// How many wakers from this probe were dropped without being woken.
+    pub fn drops(&self) -> usize {
+        self.counts.drops.load(Ordering::Relaxed)
+    }
+
+    /// How many times [`Waker::wake`] was called, consuming a waker.
+    pub fn wakes(&self) -> usize {
+        self.counts.wakes.load(Ordering::Relaxed)
+    }
+
+    /// How many times [`Waker::wake_by_ref`] was called.
+    pub fn wakes_by_ref(&self) -> usize {
+        self.counts.wakes_by_ref.load(Ordering::Relaxed)
+    }
+
+    /// How many wakers from this probe, including clones, are still alive.
+    pub fn live(&self) -> usize {
+        let counts = &self.counts;
+        let made = counts.created.load(Ordering::Relaxed) + counts.clones.load(Ordering::Relaxed);
+        made - counts.drops.load(Ordering::Relaxed) - counts.wakes.load(Ordering::Relaxed)
+    }
+}
+
+impl fmt::Debug for WakerProbe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WakerProbe")
+            .field("clones", &self.clones())
+            .field("drops", &self.drops())
+            .field("wakes", &self.wakes())
+            .field("wakes_by_ref", &self.wakes_by_ref())
+            .field("live", &self.live())
+            .finish()
+    }
+}
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop);
+
+unsafe fn clone(ptr: *const ()) -> RawWaker {
+    let counts = ptr.cast::<Counts>();
+    Arc::increment_strong_count(counts);
+    (*counts).clones.fetch_add(1, Ordering::Relaxed);
+    RawWaker::new(ptr, &VTABLE)
+}
+
+unsafe fn wake(ptr: *const ()) {
+    let counts = Arc::from_raw(ptr.cast::<Counts>());
+    counts.wakes.fetch_add(1, Ordering::Relaxed);
+}
+
+unsafe fn wake_by_ref(ptr: *const ()) {
+    (*ptr.cast::<Counts>()).wakes_by_ref.fetch_add(1, Ordering::Relaxed);
+}
+
+unsafe fn drop(ptr: *const ()) {
+    let counts = Arc::from_raw(ptr.cast::<Counts>());
+    counts.drops.fetch_add(1, Ordering::Relaxed);
+}
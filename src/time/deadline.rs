@@ -0,0 +1,244 @@
+use super::driver;
+use crate::{future::AbortHandle, Signal};
+use std::{
+    cell::RefCell,
+    fmt,
+    marker::PhantomData,
+    panic::Location,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Weak,
+    },
+    task::{Wake, Waker},
+    time::{Duration, Instant},
+};
+
+thread_local! {
+    // The deadlines entered on this thread that haven't been dropped, innermost last.
+    static DEADLINES: RefCell<Vec<Arc<Watch>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// What a [`Deadline`] does once a blocking wait outlives it.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum DeadlineAction {
+    /// Print a warning to stderr and keep waiting.
+    Log,
+    /// Abort an [`Abortable`](crate::future::Abortable) future, typically the one being waited
+    /// on, and keep waiting.
+    Abort(AbortHandle),
+    /// Panic on the blocked thread.
+    Panic,
+}
+
+struct Watch {
+    // `None` if the deadline is too far out to represent, so it never passes.
+    expires: Option<Instant>,
+    duration: Duration,
+    location: &'static Location<'static>,
+    action: DeadlineAction,
+    fired: AtomicBool,
+    // The signal of the `block_on` call parked on the thread, woken when the deadline passes. It's
+    // held weakly, since `block_on` counts strong references to tell whether it can still be woken.
+    parked: Mutex<Option<Weak<Signal>>>,
+}
+
+impl Watch {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+}
+
+// Registered with the timer thread.
+impl Wake for Watch {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let parked = self.parked.lock().unwrap().take();
+        if let Some(signal) = parked.and_then(|parked| parked.upgrade()) {
+            signal.notify();
+        }
+    }
+}
+
+/// A watchdog over every blocking wait on the current thread until it is dropped.
+///
+/// While the guard is alive, a [`block_on`](crate::block_on) call on the thread, or anything
+/// built on it, that is still waiting when the deadline passes triggers the guard's
+/// [`DeadlineAction`], once. That enforces a budget over a whole stretch of code, such as the
+/// readbacks of one frame, without wrapping each future in a [`timeout`](super::timeout). Work
+/// that doesn't block, or blocks some other way, isn't watched.
+///
+/// Deadlines can be nested; each one is watched on its own. A deadline doesn't count as a way to
+/// wake the blocked future, so one that drops its waker without waking it still panics as
+/// [`block_on`](crate::block_on) describes, rather than waiting out the deadline.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use pollster::{future::abortable, time::{Deadline, DeadlineAction}};
+///
+/// let (readback, abort) = abortable(std::future::pending::<Vec<u8>>());
+/// let deadline = Deadline::enter_with(Duration::from_millis(10), DeadlineAction::Abort(abort));
+/// assert!(pollster::block_on(readback).is_err());
+/// assert!(deadline.is_expired());
+///
+/// let forever = Deadline::enter(Duration::MAX);
+/// assert_eq!(forever.expires_at(), None);
+/// assert_eq!(pollster::block_on(async { 42 }), 42);
+/// ```
+///
+/// ```should_panic
+/// use std::{future::poll_fn, task::Poll, time::Duration};
+///
+/// let _deadline = pollster::time::Deadline::enter(Duration::from_secs(3600));
+/// pollster::block_on(poll_fn(|_| Poll::<()>::Pending));
+/// ```
+#[must_use = "the deadline is disarmed as soon as the guard is dropped"]
+pub struct Deadline {
+    watch: Arc<Watch>,
+    id: Option<u64>,
+    // Tied to the thread-local list of deadlines.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Deadline {
+    /// Arm a deadline `duration` from now that logs a warning when a blocking wait outlives it.
+    #[track_caller]
+    pub fn enter(duration: Duration) -> Self {
+        Self::enter_with(duration, DeadlineAction::Log)
+    }
+
+    /// Arm a deadline `duration` from now that takes `action` when a blocking wait outlives it.
+    ///
+    /// A `duration` too long to represent as an [`Instant`], like [`Duration::MAX`], never expires.
+    #[track_caller]
+    pub fn enter_with(duration: Duration, action: DeadlineAction) -> Self {
+        let watch = Arc::new(Watch {
+            expires: Instant::now().checked_add(duration),
+            duration,
+            location: Location::caller(),
+            action,
+            fired: AtomicBool::new(false),
+            parked: Mutex::new(None),
+        });
+        let waker = Waker::from(Arc::clone(&watch));
+        let id = watch.expires.map(|expires| driver::register(expires, &waker));
+        DEADLINES.with(|deadlines| deadlines.borrow_mut().push(Arc::clone(&watch)));
+        Self {
+            watch,
+            id,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// The instant the deadline passes at, or `None` if it never does.
+    pub fn expires_at(&self) -> Option<Instant> {
+        self.watch.expires
+    }
+
+    /// Whether the deadline has passed.
+    pub fn is_expired(&self) -> bool {
+        self.watch.is_expired(Instant::now())
+    }
+}
+
+impl Drop for Deadline {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            driver::cancel(id);
+        }
+        let _ = DEADLINES.try_with(|deadlines| {
+            deadlines.borrow_mut().retain(|watch| !Arc::ptr_eq(watch, &self.watch));
+        });
+    }
+}
+
+impl fmt::Debug for Deadline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Deadline")
+            .field("duration", &self.watch.duration)
+            .field("action", &self.watch.action)
+            .finish_non_exhaustive()
+    }
+}
+
+// Take the action of every deadline on this thread that has passed while `block_on` at `site` is
+// still waiting on the future `label` names.
+pub(crate) fn check_deadlines(site: &dyn fmt::Display, label: fn() -> String) {
+    let expired: Vec<_> = DEADLINES.with(|deadlines| {
+        let deadlines = deadlines.borrow();
+        if deadlines.is_empty() {
+            return Vec::new();
+        }
+        let now = Instant::now();
+        deadlines
+            .iter()
+            .filter(|watch| watch.is_expired(now) && !watch.fired.swap(true, Ordering::AcqRel))
+            .cloned()
+            .collect()
+    });
+    for watch in expired {
+        match &watch.action {
+            DeadlineAction::Log => eprintln!(
+                "pollster: block_on at {}: `{}` is still pending after the {:?} deadline entered at {}",
+                site,
+                label(),
+                watch.duration,
+                watch.location,
+            ),
+            DeadlineAction::Abort(handle) => handle.abort(),
+            DeadlineAction::Panic => {
+                #[cfg(feature = "trace")]
+                crate::trace::dump();
+                panic!(
+                    "block_on at {}: `{}` outlived the {:?} deadline entered at {}",
+                    site,
+                    label(),
+                    watch.duration,
+                    watch.location,
+                )
+            }
+        }
+    }
+}
+
+/// Makes the deadlines on this thread wake `signal` when they pass, until dropped.
+pub(crate) struct WatchParked(bool);
+
+impl WatchParked {
+    pub(crate) fn new(signal: &Arc<Signal>) -> Self {
+        let watched = DEADLINES.with(|deadlines| {
+            let deadlines = deadlines.borrow();
+            if deadlines.is_empty() {
+                return false;
+            }
+            let mut expired = false;
+            for watch in deadlines.iter().filter(|watch| !watch.fired.load(Ordering::Acquire)) {
+                *watch.parked.lock().unwrap() = Some(Arc::downgrade(signal));
+                // Passed before the signal was stored, so the timer thread had no one to wake.
+                expired |= watch.is_expired(Instant::now());
+            }
+            if expired {
+                signal.notify();
+            }
+            true
+        });
+        Self(watched)
+    }
+}
+
+impl Drop for WatchParked {
+    fn drop(&mut self) {
+        if self.0 {
+            DEADLINES.with(|deadlines| {
+                for watch in deadlines.borrow().iter() {
+                    watch.parked.lock().unwrap().take();
+                }
+            });
+        }
+    }
+}
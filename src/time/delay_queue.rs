@@ -0,0 +1,277 @@
+use super::{deadline_after, sleep_until, Sleep};
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// Identifies an item in a [`DelayQueue`], returned when it is inserted.
+///
+/// A key stays valid until its item expires or is removed. It is never reused for another item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DelayKey {
+    index: usize,
+    id: u64,
+}
+
+struct Entry<T> {
+    value: T,
+    deadline: Instant,
+    // Matches the key the item was inserted with.
+    id: u64,
+    // Matches the item's live heap entry; heap entries left behind by `reset` don't.
+    stamp: u64,
+}
+
+/// A queue of items that each become available once their deadline passes.
+///
+/// Items are inserted with a deadline and come out of [`pop_expired`](DelayQueue::pop_expired), or
+/// its blocking counterpart, in deadline order as they expire. Only the earliest deadline is ever
+/// registered with the timer thread, however many items are queued. Items inserted with the same
+/// deadline come out in insertion order.
+///
+/// With the `stream` feature, the queue is also a stream of the expired items.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use pollster::time::DelayQueue;
+///
+/// let mut retries = DelayQueue::new();
+/// retries.insert("second", Duration::from_millis(20));
+/// retries.insert("first", Duration::from_millis(10));
+/// let cancelled = retries.insert("never", Duration::from_millis(15));
+/// assert_eq!(retries.remove(cancelled), Some("never"));
+/// let parked = retries.insert("parked", Duration::from_millis(5));
+/// assert!(retries.reset(parked, Duration::MAX));
+/// assert_eq!(retries.remove(parked), Some("parked"));
+///
+/// assert_eq!(retries.pop_expired_blocking(), Some("first"));
+/// assert_eq!(retries.pop_expired_blocking(), Some("second"));
+/// assert_eq!(retries.pop_expired_blocking(), None);
+/// ```
+pub struct DelayQueue<T> {
+    entries: Vec<Option<Entry<T>>>,
+    free: Vec<usize>,
+    // Ordered by deadline, then by stamp so ties keep insertion order.
+    heap: BinaryHeap<Reverse<(Instant, u64, usize)>>,
+    next_id: u64,
+    len: usize,
+    // Set to the earliest deadline while an expiry is being waited for.
+    sleep: Option<Sleep>,
+}
+
+impl<T> DelayQueue<T> {
+    /// Create an empty queue.
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            free: Vec::new(),
+            heap: BinaryHeap::new(),
+            next_id: 0,
+            len: 0,
+            sleep: None,
+        }
+    }
+
+    /// Insert `value`, to expire once `delay` has passed. Like [`sleep`](super::sleep), a `delay`
+    /// too long to represent never practically expires.
+    pub fn insert(&mut self, value: T, delay: Duration) -> DelayKey {
+        self.insert_at(value, deadline_after(delay))
+    }
+
+    /// Insert `value`, to expire once `deadline` is reached.
+    pub fn insert_at(&mut self, value: T, deadline: Instant) -> DelayKey {
+        let id = self.stamp();
+        let index = self.free.pop().unwrap_or_else(|| {
+            self.entries.push(None);
+            self.entries.len() - 1
+        });
+        self.entries[index] = Some(Entry {
+            value,
+            deadline,
+            id,
+            stamp: id,
+        });
+        self.heap.push(Reverse((deadline, id, index)));
+        self.len += 1;
+        DelayKey { index, id }
+    }
+
+    /// Remove an item before it expires, returning it. Returns `None` if it has already expired
+    /// or been removed.
+    pub fn remove(&mut self, key: DelayKey) -> Option<T> {
+        self.entry(key)?;
+        let entry = self.entries[key.index].take()?;
+        self.free.push(key.index);
+        self.len -= 1;
+        self.compact();
+        Some(entry.value)
+    }
+
+    /// Move an item's deadline to `delay` from now, saturating like [`insert`](DelayQueue::insert).
+    /// Returns whether the item was still queued.
+    pub fn reset(&mut self, key: DelayKey, delay: Duration) -> bool {
+        self.reset_at(key, deadline_after(delay))
+    }
+
+    /// Move an item's deadline to `deadline`. Returns whether the item was still queued.
+    pub fn reset_at(&mut self, key: DelayKey, deadline: Instant) -> bool {
+        let stamp = self.stamp();
+        let entry = match self.entry(key) {
+            Some(entry) => entry,
+            None => return false,
+        };
+        entry.deadline = deadline;
+        entry.stamp = stamp;
+        self.heap.push(Reverse((deadline, stamp, key.index)));
+        self.compact();
+        true
+    }
+
+    /// The deadline of an item, if it is still queued.
+    pub fn deadline(&self, key: DelayKey) -> Option<Instant> {
+        match self.entries.get(key.index) {
+            Some(Some(entry)) if entry.id == key.id => Some(entry.deadline),
+            _ => None,
+        }
+    }
+
+    /// Whether an item is still queued.
+    pub fn contains(&self, key: DelayKey) -> bool {
+        self.deadline(key).is_some()
+    }
+
+    /// The number of queued items, expired or not.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove every item.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.free.clear();
+        self.heap.clear();
+        self.len = 0;
+        self.sleep = None;
+    }
+
+    /// Wait for the next item to expire and remove it, resolving to `None` straight away if the
+    /// queue is empty.
+    pub fn pop_expired(&mut self) -> PopExpired<'_, T> {
+        PopExpired { queue: self }
+    }
+
+    /// Block the current thread until the next item expires and remove it, returning `None`
+    /// straight away if the queue is empty.
+    #[track_caller]
+    pub fn pop_expired_blocking(&mut self) -> Option<T> {
+        crate::block_on(self.pop_expired())
+    }
+
+    /// Poll for the next expired item, registering the waker to hear about it if none has expired
+    /// yet. Returns `Poll::Ready(None)` if the queue is empty.
+    pub fn poll_expired(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        while let Some(&Reverse((deadline, stamp, index))) = self.heap.peek() {
+            let live = matches!(&self.entries[index], Some(entry) if entry.stamp == stamp);
+            if !live {
+                self.heap.pop();
+                continue;
+            }
+
+            if deadline > Instant::now() {
+                let sleep = self.sleep.get_or_insert_with(|| sleep_until(deadline));
+                if sleep.deadline() != deadline {
+                    sleep.reset(deadline);
+                }
+                // A sleep cut short by `shutdown` just registers again on the next pass.
+                if Pin::new(sleep).poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+                continue;
+            }
+
+            self.heap.pop();
+            let entry = self.entries[index].take().unwrap();
+            self.free.push(index);
+            self.len -= 1;
+            return Poll::Ready(Some(entry.value));
+        }
+        self.sleep = None;
+        Poll::Ready(None)
+    }
+
+    fn entry(&mut self, key: DelayKey) -> Option<&mut Entry<T>> {
+        match self.entries.get_mut(key.index) {
+            Some(Some(entry)) if entry.id == key.id => Some(entry),
+            _ => None,
+        }
+    }
+
+    fn stamp(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    // Drop the heap entries of removed and reset items once they outnumber the live ones.
+    fn compact(&mut self) {
+        if self.heap.len() > 2 * self.len + 64 {
+            let entries = &self.entries;
+            self.heap.retain(|&Reverse((_, stamp, index))| {
+                matches!(&entries[index], Some(entry) if entry.stamp == stamp)
+            });
+        }
+    }
+}
+
+impl<T> Default for DelayQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Items are never pinned.
+impl<T> Unpin for DelayQueue<T> {}
+
+impl<T> fmt::Debug for DelayQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DelayQueue").field("len", &self.len).finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T> futures_core::Stream for DelayQueue<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.get_mut().poll_expired(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+/// Future returned by [`DelayQueue::pop_expired`].
+#[derive(Debug)]
+pub struct PopExpired<'a, T> {
+    queue: &'a mut DelayQueue<T>,
+}
+
+impl<T> Future for PopExpired<'_, T> {
+    type Output = Option<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.queue.poll_expired(cx)
+    }
+}
@@ -0,0 +1,251 @@
+// The timer thread, backed by a hashed timer wheel.
+//
+// Deadlines are rounded up to whole ticks, so timers due within the same tick share one slot and
+// are woken as a batch. Each slot holds the timers whose tick hashes to it, whatever the round, so
+// registering and cancelling stay O(1) however many timers are pending.
+
+use std::{
+    collections::HashMap,
+    sync::{Condvar, Mutex, OnceLock},
+    task::Waker,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+const TICK: Duration = Duration::from_millis(1);
+const SLOTS: u64 = 512;
+
+struct Timer {
+    tick: u64,
+    waker: Waker,
+}
+
+struct DriverState {
+    // Timer ids by slot. Ids without an entry in `timers` were cancelled and are skipped.
+    slots: Vec<Vec<u64>>,
+    slot_entries: usize,
+    timers: HashMap<u64, Timer>,
+    next_id: u64,
+    // Every tick up to and including this one has fired.
+    processed: u64,
+    // The tick the thread sleeps until, if it is sleeping with a timeout.
+    sleeping_until: Option<u64>,
+    // The running timer thread. It exits once `generation` moves on from the one it started in.
+    thread: Option<JoinHandle<()>>,
+    generation: u64,
+    // Registrations with lower ids were pending when the driver was last shut down.
+    shut_down_below: u64,
+}
+
+impl DriverState {
+    fn slot(&mut self, tick: u64) -> &mut Vec<u64> {
+        &mut self.slots[(tick % SLOTS) as usize]
+    }
+
+    // Fire every timer due by `now`, collecting their wakers.
+    fn advance(&mut self, now: u64, expired: &mut Vec<Waker>) {
+        // After a long gap every slot is visited once; the absolute ticks sort out which are due.
+        let first = self.processed + 1;
+        let last = now.min(self.processed + SLOTS);
+        for tick in first..=last {
+            let mut slot = std::mem::take(self.slot(tick));
+            let before = slot.len();
+            let timers = &mut self.timers;
+            slot.retain(|id| match timers.get(id) {
+                Some(timer) if timer.tick > now => true,
+                Some(_) => {
+                    expired.extend(timers.remove(id).map(|timer| timer.waker));
+                    false
+                }
+                None => false,
+            });
+            self.slot_entries -= before - slot.len();
+            *self.slot(tick) = slot;
+        }
+        self.processed = self.processed.max(now);
+    }
+
+    // The next tick with anything in its slot, which may only be due in a later round.
+    fn next_tick(&self) -> Option<u64> {
+        if self.timers.is_empty() {
+            return None;
+        }
+        (self.processed + 1..=self.processed + SLOTS).find(|&tick| !self.slots[(tick % SLOTS) as usize].is_empty())
+    }
+
+    fn compact(&mut self) {
+        let timers = &self.timers;
+        for slot in &mut self.slots {
+            slot.retain(|id| timers.contains_key(id));
+        }
+        self.slot_entries = self.timers.len();
+    }
+}
+
+struct Driver {
+    origin: Instant,
+    state: Mutex<DriverState>,
+    cond: Condvar,
+}
+
+static DRIVER: OnceLock<Driver> = OnceLock::new();
+
+fn driver() -> &'static Driver {
+    DRIVER.get_or_init(|| Driver {
+        origin: Instant::now(),
+        state: Mutex::new(DriverState {
+            slots: (0..SLOTS).map(|_| Vec::new()).collect(),
+            slot_entries: 0,
+            timers: HashMap::new(),
+            next_id: 0,
+            processed: 0,
+            sleeping_until: None,
+            thread: None,
+            generation: 0,
+            shut_down_below: 0,
+        }),
+        cond: Condvar::new(),
+    })
+}
+
+impl Driver {
+    // The last tick that has fully passed at `instant`.
+    fn tick_floor(&self, instant: Instant) -> u64 {
+        (instant.saturating_duration_since(self.origin).as_nanos() / TICK.as_nanos()) as u64
+    }
+
+    // The first tick at or after `instant`.
+    fn tick_ceil(&self, instant: Instant) -> u64 {
+        let nanos = instant.saturating_duration_since(self.origin).as_nanos();
+        nanos.div_ceil(TICK.as_nanos()) as u64
+    }
+
+    // The instant `tick` starts at, or `None` if that's too far out to represent.
+    fn tick_instant(&self, tick: u64) -> Option<Instant> {
+        let nanos = (TICK.as_nanos() as u64).checked_mul(tick)?;
+        self.origin.checked_add(Duration::from_nanos(nanos))
+    }
+
+    fn run(&self, generation: u64) {
+        let mut expired = Vec::new();
+        let mut state = self.state.lock().unwrap();
+        while state.generation == generation {
+            let now = Instant::now();
+            state.sleeping_until = None;
+            state.advance(self.tick_floor(now), &mut expired);
+
+            // Wake outside of the lock, in case a waker re-registers a timer right away.
+            if !expired.is_empty() {
+                drop(state);
+                expired.drain(..).for_each(Waker::wake);
+                state = self.state.lock().unwrap();
+                continue;
+            }
+
+            let next = state.next_tick();
+            state = match next.and_then(|tick| self.tick_instant(tick).map(|at| (tick, at))) {
+                Some((tick, wake_at)) => {
+                    state.sleeping_until = Some(tick);
+                    self.cond.wait_timeout(state, wake_at.saturating_duration_since(now)).unwrap().0
+                }
+                // Nothing is due, or nothing that could ever come.
+                None => self.cond.wait(state).unwrap(),
+            };
+        }
+    }
+}
+
+/// Register `waker` to be woken once `deadline` has passed, returning an id for updating or
+/// cancelling the registration.
+pub(crate) fn register(deadline: Instant, waker: &Waker) -> u64 {
+    let driver = driver();
+    let mut state = driver.state.lock().unwrap();
+    let id = state.next_id;
+    state.next_id += 1;
+
+    // Cancelled registrations leave their ids behind in the slots; drop them once they pile up.
+    if state.slot_entries > 2 * state.timers.len() + 64 {
+        state.compact();
+    }
+
+    if state.thread.is_none() {
+        let generation = state.generation;
+        let thread = thread::Builder::new()
+            .name("pollster-timer".into())
+            .spawn(move || driver.run(generation))
+            .expect("failed to spawn timer thread");
+        state.thread = Some(thread);
+        crate::shutdown::stop_at_exit();
+    }
+
+    let tick = driver.tick_ceil(deadline).max(state.processed + 1);
+    state.slot(tick).push(id);
+    state.slot_entries += 1;
+    state.timers.insert(
+        id,
+        Timer {
+            tick,
+            waker: waker.clone(),
+        },
+    );
+    if state.sleeping_until.is_none_or(|until| tick < until) {
+        driver.cond.notify_one();
+    }
+    id
+}
+
+/// What became of a registration, as reported by [`update`].
+pub(crate) enum Update {
+    Pending,
+    // Fired or cancelled.
+    Gone,
+    // Dropped by `shutdown` before its deadline.
+    ShutDown,
+}
+
+/// Replace the waker of a registration that is still pending.
+pub(crate) fn update(id: u64, waker: &Waker) -> Update {
+    let mut guard = driver().state.lock().unwrap();
+    let state = &mut *guard;
+    match state.timers.get_mut(&id) {
+        Some(timer) => {
+            if !timer.waker.will_wake(waker) {
+                timer.waker = waker.clone();
+            }
+            Update::Pending
+        }
+        None if id < state.shut_down_below => Update::ShutDown,
+        None => Update::Gone,
+    }
+}
+
+/// Cancel a registration, if it hasn't fired yet.
+pub(crate) fn cancel(id: u64) {
+    driver().state.lock().unwrap().timers.remove(&id);
+}
+
+/// Stop the timer thread, waking every pending registration, and optionally wait for the thread
+/// to exit. The next registration starts a new thread.
+pub(crate) fn shutdown(wait: bool) {
+    let driver = match DRIVER.get() {
+        Some(driver) => driver,
+        None => return,
+    };
+    let mut state = driver.state.lock().unwrap();
+    let wakers: Vec<_> = state.timers.drain().map(|(_, timer)| timer.waker).collect();
+    state.slots.iter_mut().for_each(Vec::clear);
+    state.slot_entries = 0;
+    state.shut_down_below = state.next_id;
+    state.generation += 1;
+    let thread = state.thread.take();
+    driver.cond.notify_all();
+    drop(state);
+
+    wakers.into_iter().for_each(Waker::wake);
+    if let Some(thread) = thread {
+        // A waker run by the timer thread itself may be the one shutting down.
+        if wait && thread.thread().id() != thread::current().id() {
+            let _ = thread.join();
+        }
+    }
+}
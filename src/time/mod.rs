@@ -0,0 +1,237 @@
+//! Timers driven by a lazily spawned helper thread.
+
+mod deadline;
+mod delay_queue;
+mod driver;
+
+pub(crate) use deadline::{check_deadlines, WatchParked};
+pub use deadline::{Deadline, DeadlineAction};
+pub use delay_queue::{DelayKey, DelayQueue, PopExpired};
+
+use std::{
+    error::Error,
+    fmt,
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// Wait until `duration` has passed.
+///
+/// A `duration` too long to represent as an [`Instant`], like [`Duration::MAX`], waits for about
+/// 30 years instead.
+///
+/// # Example
+///
+/// ```
+/// use std::time::{Duration, Instant};
+///
+/// let start = Instant::now();
+/// pollster::block_on(pollster::time::sleep(Duration::from_millis(10)));
+/// assert!(start.elapsed() >= Duration::from_millis(10));
+///
+/// assert!(!pollster::time::sleep(Duration::MAX).is_elapsed());
+/// ```
+pub fn sleep(duration: Duration) -> Sleep {
+    sleep_until(deadline_after(duration))
+}
+
+// `duration` from now, or far enough out that it's never reached if that can't be represented.
+pub(crate) fn deadline_after(duration: Duration) -> Instant {
+    let now = Instant::now();
+    now.checked_add(duration)
+        .unwrap_or_else(|| now + Duration::from_secs(60 * 60 * 24 * 365 * 30))
+}
+
+/// Wait until `deadline` is reached.
+pub fn sleep_until(deadline: Instant) -> Sleep {
+    Sleep {
+        deadline,
+        id: None,
+        shut_down: false,
+    }
+}
+
+/// Future returned by [`sleep`] and [`sleep_until`].
+///
+/// If [`shutdown`](crate::shutdown) is called while the future is waiting, it completes early and
+/// [`is_shut_down`](Sleep::is_shut_down) tells the two apart. A [`timeout`] cut short this way
+/// fails with an [`Elapsed`] that says so too.
+#[derive(Debug)]
+pub struct Sleep {
+    deadline: Instant,
+    id: Option<u64>,
+    shut_down: bool,
+}
+
+impl Sleep {
+    /// The instant this future completes at.
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Whether the deadline has been reached.
+    pub fn is_elapsed(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    /// Whether the future completed because [`shutdown`](crate::shutdown) stopped the timer
+    /// thread, rather than because the deadline was reached.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{thread, time::Duration};
+    ///
+    /// let unloading = thread::spawn(|| {
+    ///     thread::sleep(Duration::from_millis(10));
+    ///     pollster::shutdown();
+    /// });
+    /// let mut sleep = pollster::time::sleep(Duration::from_secs(60));
+    /// pollster::block_on(&mut sleep);
+    /// assert!(sleep.is_shut_down());
+    /// unloading.join().unwrap();
+    /// ```
+    pub fn is_shut_down(&self) -> bool {
+        self.shut_down
+    }
+
+    /// Move the deadline, as if the future had been created with [`sleep_until`] instead.
+    pub fn reset(&mut self, deadline: Instant) {
+        if let Some(id) = self.id.take() {
+            driver::cancel(id);
+        }
+        self.deadline = deadline;
+        self.shut_down = false;
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.is_elapsed() {
+            if let Some(id) = self.id.take() {
+                driver::cancel(id);
+            }
+            return Poll::Ready(());
+        }
+        if let Some(id) = self.id {
+            match driver::update(id, cx.waker()) {
+                driver::Update::Pending => return Poll::Pending,
+                driver::Update::ShutDown => {
+                    self.id = None;
+                    self.shut_down = true;
+                    return Poll::Ready(());
+                }
+                driver::Update::Gone => {}
+            }
+        }
+        self.id = Some(driver::register(self.deadline, cx.waker()));
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            driver::cancel(id);
+        }
+    }
+}
+
+pub(crate) fn shutdown(wait: bool) {
+    driver::shutdown(wait);
+}
+
+/// Require `fut` to complete within `duration`.
+///
+/// Like [`sleep`], a `duration` too long to represent never practically elapses.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use pollster::time::timeout;
+///
+/// let slow = timeout(Duration::from_millis(10), std::future::pending::<()>());
+/// assert!(pollster::block_on(slow).is_err());
+///
+/// let fast = timeout(Duration::from_secs(10), async { 42 });
+/// assert_eq!(pollster::block_on(fast), Ok(42));
+///
+/// let unbounded = timeout(Duration::MAX, async { 42 });
+/// assert_eq!(pollster::block_on(unbounded), Ok(42));
+/// ```
+pub fn timeout<F: Future>(duration: Duration, fut: F) -> Timeout<F> {
+    Timeout {
+        future: fut,
+        sleep: sleep(duration),
+    }
+}
+
+/// Future returned by [`timeout`].
+#[derive(Debug)]
+pub struct Timeout<F> {
+    future: F,
+    sleep: Sleep,
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, Elapsed>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `future` is never moved out of `self`; `sleep` is `Unpin`.
+        let this = unsafe { self.get_unchecked_mut() };
+        // SAFETY: `Timeout` has no `Drop` impl and is only `Unpin` when the `future` it wraps is,
+        // so `future` stays pinned for as long as `self` is.
+        if let Poll::Ready(output) = unsafe { Pin::new_unchecked(&mut this.future) }.poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+        let sleep = &mut this.sleep;
+        Pin::new(&mut *sleep).poll(cx).map(|()| Err(Elapsed::new(sleep.is_shut_down())))
+    }
+}
+
+/// Error returned when a deadline passes before an operation completes, or when
+/// [`shutdown`](crate::shutdown) stops the timer thread first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elapsed {
+    shut_down: bool,
+}
+
+impl Elapsed {
+    pub(crate) fn new(shut_down: bool) -> Self {
+        Self { shut_down }
+    }
+
+    /// Whether the timer thread was shut down before the deadline passed.
+    pub fn is_shut_down(&self) -> bool {
+        self.shut_down
+    }
+}
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.shut_down {
+            "timer was shut down before the deadline"
+        } else {
+            "deadline has elapsed"
+        })
+    }
+}
+
+impl Error for Elapsed {}
+
+impl From<Elapsed> for io::Error {
+    fn from(elapsed: Elapsed) -> Self {
+        let kind = if elapsed.shut_down {
+            io::ErrorKind::Other
+        } else {
+            io::ErrorKind::TimedOut
+        };
+        io::Error::new(kind, elapsed)
+    }
+}
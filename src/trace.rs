@@ -0,0 +1,143 @@
+//! A timeline of recent [`block_on`](crate::block_on) events, recorded when the `trace` feature is
+//! enabled.
+//!
+//! Events from every thread go into one fixed-size ring buffer, so a snapshot taken after a hang
+//! shows what each blocked call was last doing. The timeline is also dumped to stderr when
+//! `block_on` detects that its future can never be woken.
+
+use crate::probe::Site;
+use std::{
+    collections::VecDeque,
+    fmt,
+    panic::Location,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, PoisonError,
+    },
+    thread::{self, ThreadId},
+    time::Instant,
+};
+
+const CAPACITY: usize = 4096;
+
+static EVENTS: Mutex<VecDeque<Event>> = Mutex::new(VecDeque::new());
+static NEXT_CALL: AtomicU64 = AtomicU64::new(0);
+
+/// A single recorded event.
+#[derive(Debug, Clone)]
+pub struct Event {
+    /// When the event happened.
+    pub time: Instant,
+    /// The thread the event happened on. For wakes and waker clones/drops this is the thread
+    /// touching the waker, not necessarily the one blocking.
+    pub thread: ThreadId,
+    /// Identifies the `block_on` call, unique within the process.
+    pub call: u64,
+    /// Where that `block_on` call was made.
+    pub location: &'static Location<'static>,
+    /// The name given with [`block_on_named`](crate::block_on_named), if any.
+    pub name: Option<&'static str>,
+    /// What happened.
+    pub kind: EventKind,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} block_on #{} at {}", self.thread, self.call, self.location)?;
+        if let Some(name) = self.name {
+            write!(f, " ({})", name)?;
+        }
+        write!(f, ": {:?}", self.kind)
+    }
+}
+
+/// The kinds of [`Event`] recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EventKind {
+    /// The future is about to be polled.
+    PollStart,
+    /// The future returned from a poll.
+    PollEnd {
+        /// Whether it returned `Poll::Ready`.
+        ready: bool,
+    },
+    /// The thread is about to park until woken.
+    Park,
+    /// The thread has been unparked.
+    Unpark,
+    /// The waker was woken.
+    Wake,
+    /// The waker was cloned.
+    WakerClone,
+    /// A clone of the waker was dropped.
+    WakerDrop,
+}
+
+/// Copy the recorded events, oldest first.
+///
+/// # Example
+///
+/// ```
+/// use pollster::trace::EventKind;
+///
+/// pollster::block_on(pollster::time::sleep(std::time::Duration::from_millis(1)));
+/// assert!(pollster::trace::snapshot().iter().any(|event| event.kind == EventKind::Park));
+/// ```
+pub fn snapshot() -> Vec<Event> {
+    events().iter().cloned().collect()
+}
+
+/// Discard all recorded events.
+pub fn clear() {
+    events().clear();
+}
+
+/// Print the recorded events to stderr, with times relative to the oldest one.
+pub fn dump() {
+    let events = snapshot();
+    eprintln!("pollster trace: {} event(s)", events.len());
+    if let Some(first) = events.first() {
+        let start = first.time;
+        for event in &events {
+            eprintln!("  +{:>12?} {}", event.time - start, event);
+        }
+    }
+}
+
+// Recording must keep working even if a thread panicked while holding the lock.
+fn events() -> std::sync::MutexGuard<'static, VecDeque<Event>> {
+    EVENTS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Identifies one `block_on` call in the events it records.
+#[derive(Clone, Copy)]
+pub(crate) struct Origin {
+    call: u64,
+    site: Site,
+}
+
+impl Origin {
+    pub(crate) fn new(site: Site) -> Self {
+        Self {
+            call: NEXT_CALL.fetch_add(1, Ordering::Relaxed),
+            site,
+        }
+    }
+
+    pub(crate) fn record(&self, kind: EventKind) {
+        let event = Event {
+            time: Instant::now(),
+            thread: thread::current().id(),
+            call: self.call,
+            location: self.site.location,
+            name: self.site.name,
+            kind,
+        };
+        let mut events = events();
+        if events.len() == CAPACITY {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+}
@@ -0,0 +1,172 @@
+use crate::oneshot;
+use std::{
+    collections::VecDeque,
+    future::Future,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Condvar, Mutex},
+    task::{Context, Poll},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+const MAX_THREADS: usize = 500;
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Job = Box<dyn FnOnce() + Send>;
+
+struct PoolState {
+    queue: VecDeque<Job>,
+    threads: usize,
+    idle: usize,
+    // Threads exit once the queue is empty and `generation` has moved on from the one they
+    // started in.
+    generation: u64,
+    handles: Vec<JoinHandle<()>>,
+}
+
+struct Pool {
+    state: Mutex<PoolState>,
+    cond: Condvar,
+}
+
+static POOL: Pool = Pool {
+    state: Mutex::new(PoolState {
+        queue: VecDeque::new(),
+        threads: 0,
+        idle: 0,
+        generation: 0,
+        handles: Vec::new(),
+    }),
+    cond: Condvar::new(),
+};
+
+impl Pool {
+    fn submit(&'static self, job: Job) {
+        let mut state = self.state.lock().unwrap();
+        state.queue.push_back(job);
+        if state.idle > 0 {
+            self.cond.notify_one();
+        }
+        // Woken idle threads only decrement `idle` once they run, so compare against the backlog.
+        if state.queue.len() > state.idle && state.threads < MAX_THREADS {
+            state.threads += 1;
+            state.handles.retain(|handle| !handle.is_finished());
+            let generation = state.generation;
+            let spawned = thread::Builder::new()
+                .name("pollster-unblock".into())
+                .spawn(move || self.work(generation));
+            match spawned {
+                Ok(handle) => {
+                    state.handles.push(handle);
+                    crate::shutdown::stop_at_exit();
+                }
+                // The threads already running get to the job eventually. Without any, it would
+                // never run, so take it back out and fail the call instead.
+                Err(err) => {
+                    state.threads -= 1;
+                    if state.threads == 0 {
+                        let job = state.queue.pop_back();
+                        drop(state);
+                        drop(job);
+                        panic!("failed to spawn unblock thread: {}", err);
+                    }
+                }
+            }
+        }
+    }
+
+    fn work(&self, generation: u64) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(job) = state.queue.pop_front() {
+                drop(state);
+                job();
+                state = self.state.lock().unwrap();
+                continue;
+            } else if state.generation != generation {
+                state.threads -= 1;
+                return;
+            }
+
+            state.idle += 1;
+            let (next, timeout) = self.cond.wait_timeout(state, IDLE_TIMEOUT).unwrap();
+            state = next;
+            state.idle -= 1;
+            if timeout.timed_out() && state.queue.is_empty() {
+                state.threads -= 1;
+                return;
+            }
+        }
+    }
+
+    // Make every thread exit once the queue is drained, optionally waiting for them to.
+    fn shutdown(&self, wait: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.generation += 1;
+        let handles = mem::take(&mut state.handles);
+        self.cond.notify_all();
+        drop(state);
+
+        if wait {
+            let current = thread::current().id();
+            for handle in handles {
+                // A job may be the one shutting down.
+                if handle.thread().id() != current {
+                    let _ = handle.join();
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn shutdown(wait: bool) {
+    POOL.shutdown(wait);
+}
+
+/// Run a blocking closure on a shared pool of helper threads, returning a future of its result.
+///
+/// Threads are started on demand (up to 500) and exit after sitting idle for a while. If the
+/// closure panics, the panic is resumed when the future is polled.
+///
+/// # Panics
+///
+/// Panics if no helper thread is running and the OS refuses to start one. While some are running,
+/// a failed start just leaves the closure queued for them.
+///
+/// # Example
+///
+/// ```
+/// let sum = pollster::block_on(pollster::unblock(|| (1..=10).sum::<u32>()));
+/// assert_eq!(sum, 55);
+/// ```
+pub fn unblock<T, F>(f: F) -> Unblock<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    #[cfg(feature = "stats")]
+    crate::stats::record_spawn();
+    let (tx, rx) = oneshot::channel();
+    POOL.submit(Box::new(move || tx.send(panic::catch_unwind(AssertUnwindSafe(f)))));
+    Unblock { rx }
+}
+
+/// Future returned by [`unblock`].
+pub struct Unblock<T> {
+    rx: oneshot::Receiver<thread::Result<T>>,
+}
+
+impl<T> Future for Unblock<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        Pin::new(&mut self.rx).poll(cx).map(|result| {
+            match result.expect("unblock job was dropped without running") {
+                Ok(value) => value,
+                Err(payload) => panic::resume_unwind(payload),
+            }
+        })
+    }
+}